@@ -0,0 +1,106 @@
+use super::error::{column, number};
+use super::ParseError;
+
+use cgmath::Vector2;
+use std::fmt;
+use std::str::FromStr;
+
+/// The `0,0,"bg.jpg",x,y` background line of a beatmap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackgroundEvent {
+    pub file_path: String,
+    pub offset: Vector2<f32>,
+}
+
+/// The `Video,time,"video.avi",x,y` line of a beatmap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VideoEvent {
+    pub time: i32,
+    pub file_path: String,
+    pub offset: Vector2<f32>,
+}
+
+/// A `2,start,end` break period of a beatmap.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BreakEvent {
+    pub start_time: i32,
+    pub end_time: i32,
+}
+
+fn parse_offset(columns: &[&str]) -> Result<Vector2<f32>, ParseError> {
+    let mut offset = Vector2::<f32>::new(0.0, 0.0);
+    if columns.len() > 3 {
+        offset.x = number::<f32>(columns, 3)?;
+    }
+    if columns.len() > 4 {
+        offset.y = number::<f32>(columns, 4)?;
+    }
+
+    Ok(offset)
+}
+
+impl FromStr for BackgroundEvent {
+    type Err = ParseError;
+
+    fn from_str(data: &str) -> Result<BackgroundEvent, ParseError> {
+        let columns: Vec<&str> = data.split(',').collect();
+
+        Ok(BackgroundEvent {
+            file_path: column(&columns, 2)?.trim_matches('"').to_string(),
+            offset: parse_offset(&columns)?,
+        })
+    }
+}
+
+impl FromStr for VideoEvent {
+    type Err = ParseError;
+
+    fn from_str(data: &str) -> Result<VideoEvent, ParseError> {
+        let columns: Vec<&str> = data.split(',').collect();
+
+        Ok(VideoEvent {
+            time: number::<i32>(&columns, 1)?,
+            file_path: column(&columns, 2)?.trim_matches('"').to_string(),
+            offset: parse_offset(&columns)?,
+        })
+    }
+}
+
+impl FromStr for BreakEvent {
+    type Err = ParseError;
+
+    fn from_str(data: &str) -> Result<BreakEvent, ParseError> {
+        let columns: Vec<&str> = data.split(',').collect();
+
+        Ok(BreakEvent {
+            start_time: number::<i32>(&columns, 1)?,
+            end_time: number::<i32>(&columns, 2)?,
+        })
+    }
+}
+
+impl fmt::Display for BackgroundEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "0,0,\"{}\",{},{}",
+            self.file_path, self.offset.x, self.offset.y
+        )
+    }
+}
+
+impl fmt::Display for VideoEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Video,{},\"{}\",{},{}",
+            self.time, self.file_path, self.offset.x, self.offset.y
+        )
+    }
+}
+
+impl fmt::Display for BreakEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "2,{},{}", self.start_time, self.end_time)
+    }
+}
@@ -0,0 +1,384 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use storyboard::{lint, Diagnostic, Lint, LoadProfile, Severity, Storyboard};
+#[cfg(feature = "render")]
+use storyboard::{LayerType, Renderer};
+
+const USAGE: &str = "usage: storyboard-cli <command> [options] <file>...
+
+Storyboards are read from `.osb` files and the `[Events]` of `.osu` files,
+passing one of each plays them together the way osu! does.
+
+commands:
+    lint      report problems osu!'s ranking criteria care about
+    render    write a PNG frame sequence, needs the `render` feature
+
+lint options:
+    --format <format>   `text` or `json`, defaults to `text`
+    --folder <dir>      beatmap folder, defaults to the directory of each file
+    --sb-load <load>    report where the storyboard load exceeds this value
+
+render options:
+    --start <ms>        first frame, defaults to the storyboard start
+    --end <ms>          stop before this time, defaults to the storyboard end
+    --fps <n>           frames per second, defaults to 30
+    --layer <name>      only draw Background, Fail, Pass, Foreground or Overlay
+    --assets <dir>      image directory, defaults to the storyboard directory
+    --widescreen        render 854x480 instead of 640x480
+    --failing           draw the Fail layer instead of the Pass layer
+    -o, --out <dir>     output directory, defaults to `frames`";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let result = match args.first().map(String::as_str) {
+        Some("lint") => lint_files(&args[1..]),
+        Some("render") => render(&args[1..]),
+
+        _ => Err(USAGE.to_string()),
+    };
+
+    if let Err(error) = result {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
+
+/// Command line split into `--flag value` options and positional files.
+struct Arguments {
+    options: Vec<(String, Option<String>)>,
+    files: Vec<PathBuf>,
+}
+
+impl Arguments {
+    /// `flags` are the options without a value.
+    fn parse(args: &[String], flags: &[&str]) -> Result<Arguments, String> {
+        let mut options = Vec::<(String, Option<String>)>::new();
+        let mut files = Vec::<PathBuf>::new();
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if !arg.starts_with('-') {
+                files.push(PathBuf::from(arg));
+            } else if flags.contains(&arg.as_str()) {
+                options.push((arg.clone(), None));
+            } else {
+                match args.next() {
+                    Some(value) => options.push((arg.clone(), Some(value.clone()))),
+                    None => return Err(format!("missing value for `{}`", arg)),
+                }
+            }
+        }
+
+        if files.is_empty() {
+            return Err(USAGE.to_string());
+        }
+
+        Ok(Arguments { options, files })
+    }
+
+    #[cfg_attr(not(feature = "render"), allow(dead_code))]
+    fn flag(&self, names: &[&str]) -> bool {
+        self.options
+            .iter()
+            .any(|(name, _)| names.contains(&name.as_str()))
+    }
+
+    fn value(&self, names: &[&str]) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(name, _)| names.contains(&name.as_str()))
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    fn number(&self, names: &[&str]) -> Result<Option<f64>, String> {
+        match self.value(names) {
+            Some(value) => value
+                .parse::<f64>()
+                .map(Some)
+                .map_err(|_| format!("invalid number for `{}`: `{}`", names[0], value)),
+            None => Ok(None),
+        }
+    }
+
+    fn check(&self, known: &[&str]) -> Result<(), String> {
+        match self
+            .options
+            .iter()
+            .find(|(name, _)| !known.contains(&name.as_str()))
+        {
+            Some((name, _)) => Err(format!("unknown option `{}`", name)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Reads a single storyboard, malformed lines end up in the diagnostics.
+fn parse(file: &Path) -> Result<(Storyboard, Vec<Diagnostic>), String> {
+    let data =
+        fs::read_to_string(file).map_err(|error| format!("{}: {}", file.display(), error))?;
+
+    Ok(match is_beatmap(file) {
+        true => Storyboard::parse_beatmap_lenient(&data),
+        false => Storyboard::parse_lenient(&data),
+    })
+}
+
+/// Reads and merges the storyboards in `files`, malformed lines are skipped.
+#[cfg(feature = "render")]
+fn load(files: &[PathBuf]) -> Result<Storyboard, String> {
+    let mut osb = Storyboard::new();
+    let mut osu = Storyboard::new();
+
+    for file in files {
+        let (storyboard, diagnostics) = parse(file)?;
+        for diagnostic in diagnostics {
+            eprintln!("{}: {}", file.display(), diagnostic);
+        }
+
+        match is_beatmap(file) {
+            true => osu = Storyboard::merge(osu, storyboard),
+            false => osb = Storyboard::merge(osb, storyboard),
+        }
+    }
+
+    Ok(Storyboard::merge(osb, osu))
+}
+
+fn is_beatmap(file: &Path) -> bool {
+    file.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("osu"))
+}
+
+/// Parse diagnostics and lints of a single file.
+struct Report {
+    file: PathBuf,
+    diagnostics: Vec<Diagnostic>,
+    lints: Vec<Lint>,
+    /// Time spans where the storyboard load is above the `--sb-load` limit.
+    load: Vec<String>,
+}
+
+fn lint_files(args: &[String]) -> Result<(), String> {
+    let args = Arguments::parse(args, &[])?;
+    args.check(&["--format", "--folder", "--sb-load"])?;
+    let threshold = args.number(&["--sb-load"])?;
+
+    let json = match args.value(&["--format"]).unwrap_or("text") {
+        "text" => false,
+        "json" => true,
+
+        format => return Err(format!("unknown format `{}`", format)),
+    };
+
+    let mut reports = Vec::<Report>::new();
+    for file in &args.files {
+        let folder = match args.value(&["--folder"]) {
+            Some(folder) => PathBuf::from(folder),
+            None => file.parent().map(Path::to_path_buf).unwrap_or_default(),
+        };
+
+        let (storyboard, diagnostics) = parse(file)?;
+        let load = match threshold {
+            Some(threshold) => load_spans(&storyboard.load_profile(&folder), threshold),
+            None => Vec::<String>::new(),
+        };
+
+        reports.push(Report {
+            file: file.clone(),
+            lints: lint(&storyboard, Some(&folder)),
+            diagnostics,
+            load,
+        });
+    }
+
+    match json {
+        true => println!("{}", to_json(&reports)),
+        false => {
+            for report in &reports {
+                for diagnostic in &report.diagnostics {
+                    println!("{}: {}", report.file.display(), diagnostic);
+                }
+                for lint in &report.lints {
+                    println!("{}: {}", report.file.display(), lint);
+                }
+                for load in &report.load {
+                    println!(
+                        "{}: warning: {} [storyboard-load]",
+                        report.file.display(),
+                        load
+                    );
+                }
+            }
+        }
+    }
+
+    let errors = reports.iter().any(|report| {
+        report
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.severity)
+            .chain(report.lints.iter().map(Lint::severity))
+            .any(|severity| severity == Severity::Error)
+    });
+    match errors {
+        true => Err("storyboard has errors".to_string()),
+        false => Ok(()),
+    }
+}
+
+/// One object per problem, parse diagnostics carry a `line` and lints an
+/// `event` index, the other one is `null`.
+fn to_json(reports: &[Report]) -> String {
+    let mut problems = Vec::<String>::new();
+
+    for report in reports {
+        let file = json_string(&report.file.to_string_lossy());
+
+        for diagnostic in &report.diagnostics {
+            problems.push(format!(
+                "{{\"file\":{},\"line\":{},\"event\":null,\"kind\":\"parse-error\",\"severity\":\"{}\",\"message\":{}}}",
+                file,
+                diagnostic.line,
+                diagnostic.severity,
+                json_string(&diagnostic.message)
+            ));
+        }
+        for lint in &report.lints {
+            problems.push(format!(
+                "{{\"file\":{},\"line\":null,\"event\":{},\"kind\":\"{}\",\"severity\":\"{}\",\"message\":{}}}",
+                file,
+                lint.event,
+                lint.kind.name(),
+                lint.severity(),
+                json_string(&lint.message)
+            ));
+        }
+        for load in &report.load {
+            problems.push(format!(
+                "{{\"file\":{},\"line\":null,\"event\":null,\"kind\":\"storyboard-load\",\"severity\":\"warning\",\"message\":{}}}",
+                file,
+                json_string(load)
+            ));
+        }
+    }
+
+    format!("[{}]", problems.join(","))
+}
+
+/// Describes every run of consecutive samples above `threshold`.
+fn load_spans(profile: &LoadProfile, threshold: f64) -> Vec<String> {
+    let mut spans = Vec::<String>::new();
+    let mut span: Option<(i32, i32, f64, i32)> = None;
+
+    for sample in profile.samples.iter().map(Some).chain(Some(None)) {
+        match (sample, span) {
+            (Some(sample), Some((start, _, peak, peak_time))) if sample.load > threshold => {
+                span = match sample.load > peak {
+                    true => Some((start, sample.time, sample.load, sample.time)),
+                    false => Some((start, sample.time, peak, peak_time)),
+                };
+            }
+            (Some(sample), None) if sample.load > threshold => {
+                span = Some((sample.time, sample.time, sample.load, sample.time));
+            }
+            (_, Some((start, end, peak, peak_time))) => {
+                spans.push(format!(
+                    "storyboard load above {} from {}ms to {}ms, peaking at {:.2} at {}ms",
+                    threshold, start, end, peak, peak_time
+                ));
+                span = None;
+            }
+
+            _ => {}
+        }
+    }
+
+    spans
+}
+
+fn json_string(text: &str) -> String {
+    let mut json = String::with_capacity(text.len() + 2);
+    json.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+
+    json
+}
+
+#[cfg(feature = "render")]
+fn render(args: &[String]) -> Result<(), String> {
+    let args = Arguments::parse(args, &["--widescreen", "--failing"])?;
+    args.check(&[
+        "--start",
+        "--end",
+        "--fps",
+        "--layer",
+        "--assets",
+        "--widescreen",
+        "--failing",
+        "-o",
+        "--out",
+    ])?;
+
+    let storyboard = load(&args.files)?;
+    let (start_time, end_time) = storyboard.command_range().unwrap_or((0, 0));
+
+    let start = args.number(&["--start"])?.unwrap_or(f64::from(start_time));
+    let end = args.number(&["--end"])?.unwrap_or(f64::from(end_time));
+    let fps = args.number(&["--fps"])?.unwrap_or(30.0);
+    if fps <= 0.0 {
+        return Err(format!("invalid frame rate `{}`", fps));
+    }
+
+    let assets = match args.value(&["--assets"]) {
+        Some(assets) => PathBuf::from(assets),
+        None => args.files[0]
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default(),
+    };
+    let out_dir = args.value(&["-o", "--out"]).unwrap_or("frames");
+
+    let mut renderer = Renderer::new(assets)
+        .widescreen(args.flag(&["--widescreen"]))
+        .failing(args.flag(&["--failing"]));
+    if let Some(layer) = args.value(&["--layer"]) {
+        renderer = renderer.layer(match layer {
+            "Background" => LayerType::Background,
+            "Fail" => LayerType::Fail,
+            "Pass" => LayerType::Pass,
+            "Foreground" => LayerType::Foreground,
+            "Overlay" => LayerType::Overlay,
+
+            _ => return Err(format!("unknown layer `{}`", layer)),
+        });
+    }
+
+    let frames = renderer
+        .render_sequence(&storyboard, start, end, fps, out_dir)
+        .map_err(|error| format!("{}: {}", out_dir, error))?;
+    println!("wrote {} frames to {}", frames, out_dir);
+
+    Ok(())
+}
+
+#[cfg(not(feature = "render"))]
+fn render(_: &[String]) -> Result<(), String> {
+    Err("storyboard-cli was built without the `render` feature".to_string())
+}
@@ -0,0 +1,53 @@
+use cgmath::Vector3;
+
+/// A colour as written in `C` commands, every channel in `0.0..=255.0`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Rgb {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+}
+
+impl Rgb {
+    /// Sprites without colour commands are drawn untinted.
+    pub const WHITE: Rgb = Rgb {
+        r: 255.0,
+        g: 255.0,
+        b: 255.0,
+    };
+
+    pub fn new(r: f32, g: f32, b: f32) -> Rgb {
+        Rgb { r, g, b }
+    }
+
+    /// The sRGB encoded channels normalized to `0.0..=1.0`, the way osu!
+    /// tints textures.
+    pub fn to_srgb(self) -> Vector3<f32> {
+        Vector3::<f32>::new(self.r, self.g, self.b) / 255.0
+    }
+
+    /// The channels normalized to `0.0..=1.0` and decoded to linear light.
+    pub fn to_linear(self) -> Vector3<f32> {
+        let srgb = self.to_srgb();
+
+        Vector3::<f32>::new(
+            srgb_to_linear(srgb.x),
+            srgb_to_linear(srgb.y),
+            srgb_to_linear(srgb.z),
+        )
+    }
+}
+
+impl Default for Rgb {
+    fn default() -> Rgb {
+        Rgb::WHITE
+    }
+}
+
+fn srgb_to_linear(channel: f32) -> f32 {
+    if channel <= 0.04045 {
+        channel / 12.92
+    } else {
+        ((channel + 0.055) / 1.055).powf(2.4)
+    }
+}
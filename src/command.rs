@@ -1,8 +1,14 @@
-use super::CommandGroup;
+use super::error::{column, number};
+use super::{
+    CommandGroup, Easing, Parameter, ParameterKind, ParseError, ParseErrorKind, Rgb,
+    TriggerCondition,
+};
 
-use cgmath::{Vector2, Vector4};
+use cgmath::Vector2;
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[allow(dead_code)]
 pub enum CommandType {
     None,
@@ -19,325 +25,316 @@ pub enum CommandType {
     BlendingMode,
 }
 
-#[derive(Debug, Copy, Clone)]
-#[allow(dead_code)]
-pub enum Easing {
-    None,
-    Out,
-    In,
-    InQuad,
-    OutQuad,
-    InOutQuad,
-    InCubic,
-    OutCubic,
-    InOutCubic,
-    InQuart,
-    OutQuart,
-    InOutQuart,
-    InQuint,
-    OutQuint,
-    InOutQuint,
-    InSine,
-    OutSine,
-    InOutSine,
-    InExpo,
-    OutExpo,
-    InOutExpo,
-    InCirc,
-    OutCirc,
-    InOutCirc,
-    InElastic,
-    OutElastic,
-    OutElasticHalf,
-    OutElasticQuarter,
-    InOutElastic,
-    InBack,
-    OutBack,
-    InOutBack,
-    InBounce,
-    OutBounce,
-    InOutBounce,
+/// The start and end values of a command, only parameters have none.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Payload {
+    Move(Vector2<f32>, Vector2<f32>),
+    MoveX(f32, f32),
+    MoveY(f32, f32),
+    Fade(f32, f32),
+    Scale(f32, f32),
+    VectorScale(Vector2<f32>, Vector2<f32>),
+    Rotation(f32, f32),
+    Colour(Rgb, Rgb),
+    Parameter(ParameterKind),
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Command {
-    pub ctype: CommandType,
     pub easing: Easing,
     pub start_time: i32,
     pub end_time: i32,
-    pub start_colour: Vector4<f32>,
-    pub end_colour: Vector4<f32>,
-    pub start_vector: Vector2<f32>,
-    pub end_vector: Vector2<f32>,
-    pub start_value: f32,
-    pub end_value: f32,
+    pub payload: Payload,
 }
 
 impl Command {
-    pub fn from_string(data: String) -> Command {
-        let mut columns: Vec<&str> = data.split(",").collect();
+    pub fn new(payload: Payload, easing: Easing, start_time: i32, end_time: i32) -> Command {
+        Command {
+            easing,
+            start_time,
+            end_time,
+            payload,
+        }
+    }
 
-        if columns.len() < 4 {
-            columns.push("")
+    pub fn ctype(&self) -> CommandType {
+        match self.payload {
+            Payload::Move(..) => CommandType::Movement,
+            Payload::MoveX(..) => CommandType::MovementX,
+            Payload::MoveY(..) => CommandType::MovementY,
+            Payload::Fade(..) => CommandType::Fade,
+            Payload::Scale(..) => CommandType::Scale,
+            Payload::VectorScale(..) => CommandType::VectorScale,
+            Payload::Rotation(..) => CommandType::Rotation,
+            Payload::Colour(..) => CommandType::Colour,
+            Payload::Parameter(ParameterKind::FlipHorizontal) => CommandType::FlipHorizontal,
+            Payload::Parameter(ParameterKind::FlipVertical) => CommandType::FlipVertical,
+            Payload::Parameter(ParameterKind::AdditiveBlending) => CommandType::BlendingMode,
         }
-        if columns[3] == "" {
-            columns[3] = columns[2]
+    }
+
+    /// The start value of `F`, `S` and `R` commands.
+    pub fn start_value(&self) -> Option<f32> {
+        self.values().map(|(start, _)| start)
+    }
+
+    /// The end value of `F`, `S` and `R` commands.
+    pub fn end_value(&self) -> Option<f32> {
+        self.values().map(|(_, end)| end)
+    }
+
+    fn values(&self) -> Option<(f32, f32)> {
+        match self.payload {
+            Payload::Fade(start, end)
+            | Payload::Scale(start, end)
+            | Payload::Rotation(start, end) => Some((start, end)),
+
+            _ => None,
         }
+    }
 
-        let easing = match columns[1] {
-            "1" => Easing::Out,
-            "2" => Easing::In,
-            "3" => Easing::InQuad,
-            "4" => Easing::OutQuad,
-            "5" => Easing::InOutQuad,
-            "6" => Easing::InCubic,
-            "7" => Easing::OutCubic,
-            "8" => Easing::InOutCubic,
-            "9" => Easing::InQuart,
-            "10" => Easing::OutQuart,
-            "11" => Easing::InOutQuart,
-            "12" => Easing::InQuint,
-            "13" => Easing::OutQuint,
-            "14" => Easing::InOutQuint,
-            "15" => Easing::InSine,
-            "16" => Easing::OutSine,
-            "17" => Easing::InOutSine,
-            "18" => Easing::InExpo,
-            "19" => Easing::OutExpo,
-            "20" => Easing::InOutExpo,
-            "21" => Easing::InCirc,
-            "22" => Easing::OutCirc,
-            "23" => Easing::InOutCirc,
-            "24" => Easing::InElastic,
-            "25" => Easing::OutElastic,
-            "26" => Easing::OutElasticHalf,
-            "27" => Easing::OutElasticQuarter,
-            "28" => Easing::InOutElastic,
-            "29" => Easing::InBack,
-            "30" => Easing::OutBack,
-            "31" => Easing::InOutBack,
-            "32" => Easing::InBounce,
-            "33" => Easing::OutBounce,
-            "34" => Easing::InOutBounce,
-
-            _ => Easing::None,
-        };
+    /// The start vector of `M`, `MX`, `MY` and `V` commands, the axis a
+    /// single axis movement leaves alone is 0.
+    pub fn start_vector(&self) -> Option<Vector2<f32>> {
+        self.vectors().map(|(start, _)| start)
+    }
 
-        let start_time = columns[2].parse::<i32>().unwrap();
-        let end_time = columns[3].parse::<i32>().unwrap();
+    /// The end vector of `M`, `MX`, `MY` and `V` commands, see `start_vector`.
+    pub fn end_vector(&self) -> Option<Vector2<f32>> {
+        self.vectors().map(|(_, end)| end)
+    }
 
-        match columns[0] {
-            "F" => {
-                let start_value = columns[4].parse::<f32>().unwrap();
-                let mut end_value = 0.0f32;
-                if columns.len() > 5 {
-                    end_value = columns[4].parse::<f32>().unwrap();
-                }
-                Command {
-                    ctype: CommandType::Fade,
-                    easing,
-                    start_time,
-                    end_time,
-                    start_colour: Vector4::<f32>::new(0.0, 0.0, 0.0, 0.0),
-                    end_colour: Vector4::<f32>::new(0.0, 0.0, 0.0, 0.0),
-                    start_vector: Vector2::<f32>::new(0.0, 0.0),
-                    end_vector: Vector2::<f32>::new(0.0, 0.0),
-                    start_value,
-                    end_value,
-                }
-            }
-            "M" => {
-                let start_x = columns[4].parse::<f32>().unwrap();
-                let start_y = columns[5].parse::<f32>().unwrap();
-
-                let mut end_x = start_x;
-                let mut end_y = start_y;
-                if columns.len() > 6 {
-                    end_x = columns[6].parse::<f32>().unwrap();
-                }
+    fn vectors(&self) -> Option<(Vector2<f32>, Vector2<f32>)> {
+        match self.payload {
+            Payload::Move(start, end) | Payload::VectorScale(start, end) => Some((start, end)),
+            Payload::MoveX(start, end) => Some((
+                Vector2::<f32>::new(start, 0.0),
+                Vector2::<f32>::new(end, 0.0),
+            )),
+            Payload::MoveY(start, end) => Some((
+                Vector2::<f32>::new(0.0, start),
+                Vector2::<f32>::new(0.0, end),
+            )),
+
+            _ => None,
+        }
+    }
 
-                if columns.len() > 7 {
-                    end_y = columns[7].parse::<f32>().unwrap();
-                }
+    pub fn start_colour(&self) -> Option<Rgb> {
+        match self.payload {
+            Payload::Colour(start, _) => Some(start),
 
-                Command {
-                    ctype: CommandType::Movement,
-                    easing,
-                    start_time,
-                    end_time,
-                    start_colour: Vector4::<f32>::new(0.0, 0.0, 0.0, 0.0),
-                    end_colour: Vector4::<f32>::new(0.0, 0.0, 0.0, 0.0),
-                    start_vector: Vector2::<f32>::new(start_x, start_y),
-                    end_vector: Vector2::<f32>::new(end_x, end_y),
-                    start_value: 0.0,
-                    end_value: 0.0,
-                }
-            }
-            "MX" => {
-                let start_x = columns[4].parse::<f32>().unwrap();
+            _ => None,
+        }
+    }
 
-                let mut end_x = start_x;
-                if columns.len() > 5 {
-                    end_x = columns[5].parse::<f32>().unwrap();
-                }
+    pub fn end_colour(&self) -> Option<Rgb> {
+        match self.payload {
+            Payload::Colour(_, end) => Some(end),
 
-                Command {
-                    ctype: CommandType::MovementX,
-                    easing,
-                    start_time,
-                    end_time,
-                    start_colour: Vector4::<f32>::new(0.0, 0.0, 0.0, 0.0),
-                    end_colour: Vector4::<f32>::new(0.0, 0.0, 0.0, 0.0),
-                    start_vector: Vector2::<f32>::new(start_x, 0.0),
-                    end_vector: Vector2::<f32>::new(end_x, 0.0),
-                    start_value: 0.0,
-                    end_value: 0.0,
-                }
-            }
-            "MY" => {
-                let start_y = columns[4].parse::<f32>().unwrap();
+            _ => None,
+        }
+    }
 
-                let mut end_y = start_y;
+    pub fn parameter(&self) -> Option<Parameter> {
+        match self.payload {
+            Payload::Parameter(kind) => Some(Parameter {
+                kind,
+                start_time: self.start_time,
+                end_time: self.end_time,
+            }),
 
-                if columns.len() > 5 {
-                    end_y = columns[5].parse::<f32>().unwrap();
-                }
+            _ => None,
+        }
+    }
 
-                Command {
-                    ctype: CommandType::MovementY,
-                    easing,
-                    start_time,
-                    end_time,
-                    start_colour: Vector4::<f32>::new(0.0, 0.0, 0.0, 0.0),
-                    end_colour: Vector4::<f32>::new(0.0, 0.0, 0.0, 0.0),
-                    start_vector: Vector2::<f32>::new(0.0, start_y),
-                    end_vector: Vector2::<f32>::new(0.0, end_y),
-                    start_value: 0.0,
-                    end_value: 0.0,
-                }
-            }
-            "S" => {
-                let start_value = columns[4].parse::<f32>().unwrap();
-                let mut end_value = 0.0f32;
-                if columns.len() > 5 {
-                    end_value = columns[4].parse::<f32>().unwrap();
-                }
-                Command {
-                    ctype: CommandType::Scale,
-                    easing,
-                    start_time,
-                    end_time,
-                    start_colour: Vector4::<f32>::new(0.0, 0.0, 0.0, 0.0),
-                    end_colour: Vector4::<f32>::new(0.0, 0.0, 0.0, 0.0),
-                    start_vector: Vector2::<f32>::new(0.0, 0.0),
-                    end_vector: Vector2::<f32>::new(0.0, 0.0),
-                    start_value,
-                    end_value,
-                }
-            }
-            "V" => {
-                let start_x = columns[4].parse::<f32>().unwrap();
-                let start_y = columns[5].parse::<f32>().unwrap();
-
-                let mut end_x = start_x;
-                let mut end_y = start_y;
-                if columns.len() > 6 {
-                    end_x = columns[6].parse::<f32>().unwrap();
-                }
+    /// # Panics
+    ///
+    /// Panics if `data` is not a valid command, use `str::parse` to handle errors.
+    #[deprecated(
+        since = "0.1.1",
+        note = "panics on malformed commands, use `str::parse`"
+    )]
+    pub fn from_string(data: String) -> Command {
+        data.parse::<Command>().unwrap()
+    }
 
-                if columns.len() > 7 {
-                    end_y = columns[7].parse::<f32>().unwrap();
-                }
+    /// Parses a command line, expanding osu!'s shorthand: every value group
+    /// after the end values starts another segment of the same duration, so
+    /// `F,0,1000,2000,0,1,0.5` fades from 0 to 1 and then from 1 to 0.5
+    /// between 2000 and 3000. An incomplete last group repeats the missing
+    /// values of the group before it.
+    pub fn parse_line(data: &str) -> Result<Vec<Command>, ParseError> {
+        let columns: Vec<&str> = data.split(',').collect();
+
+        // osu! plays unknown easings linearly
+        let easing = Easing::from_id(number::<i32>(&columns, 1)?).unwrap_or(Easing::None);
+
+        let start_time = number::<i32>(&columns, 2)?;
+        let end_time = match columns.get(3) {
+            Some(column) if !column.is_empty() => number::<i32>(&columns, 3)?,
+            _ => start_time,
+        };
 
-                Command {
-                    ctype: CommandType::VectorScale,
-                    easing,
-                    start_time,
-                    end_time,
-                    start_colour: Vector4::<f32>::new(0.0, 0.0, 0.0, 0.0),
-                    end_colour: Vector4::<f32>::new(0.0, 0.0, 0.0, 0.0),
-                    start_vector: Vector2::<f32>::new(start_x, start_y),
-                    end_vector: Vector2::<f32>::new(end_x, end_y),
-                    start_value: 0.0,
-                    end_value: 0.0,
-                }
+        let (ctype, arity) = match columns[0] {
+            "F" => (CommandType::Fade, 1),
+            "S" => (CommandType::Scale, 1),
+            "R" => (CommandType::Rotation, 1),
+            "MX" => (CommandType::MovementX, 1),
+            "MY" => (CommandType::MovementY, 1),
+            "M" => (CommandType::Movement, 2),
+            "V" => (CommandType::VectorScale, 2),
+            "C" => (CommandType::Colour, 3),
+            "P" => {
+                let name = column(&columns, 4)?;
+                let kind = match ParameterKind::from_name(name.trim()) {
+                    Some(kind) => kind,
+                    None => return Err(ParseError::new(ParseErrorKind::UnknownCommand, 4, name)),
+                };
+
+                let payload = Payload::Parameter(kind);
+                return Ok(vec![Command::new(payload, easing, start_time, end_time)]);
             }
-            "R" => {
-                let start_value = columns[4].parse::<f32>().unwrap();
-                let mut end_value = 0.0f32;
-                if columns.len() > 5 {
-                    end_value = columns[4].parse::<f32>().unwrap();
-                }
-                Command {
-                    ctype: CommandType::Rotation,
-                    easing,
-                    start_time,
-                    end_time,
-                    start_colour: Vector4::<f32>::new(0.0, 0.0, 0.0, 0.0),
-                    end_colour: Vector4::<f32>::new(0.0, 0.0, 0.0, 0.0),
-                    start_vector: Vector2::<f32>::new(0.0, 0.0),
-                    end_vector: Vector2::<f32>::new(0.0, 0.0),
-                    start_value,
-                    end_value,
+
+            ctype => return Err(ParseError::new(ParseErrorKind::UnknownCommand, 0, ctype)),
+        };
+
+        // Trailing empty columns are left out, the start values are required
+        let mut count = columns.len();
+        while count > 4 + arity && columns[count - 1].trim().is_empty() {
+            count -= 1;
+        }
+
+        let mut values = Vec::<[f32; 3]>::new();
+        let mut index = 4;
+        while values.is_empty() || index < count {
+            let mut value = values.last().copied().unwrap_or([0.0; 3]);
+            for channel in value.iter_mut().take(arity) {
+                if values.is_empty() || index < count {
+                    *channel = number::<f32>(&columns, index)?;
                 }
+                index += 1;
             }
-            "C" => {
-                let start_r = columns[4].parse::<f32>().unwrap();
-                let start_g = columns[5].parse::<f32>().unwrap();
-                let start_b = columns[6].parse::<f32>().unwrap();
 
-                let mut end_r = 0.0f32;
-                let mut end_g = 0.0f32;
-                let mut end_b = 0.0f32;
+            values.push(value);
+        }
 
-                if columns.len() > 7 {
-                    end_r = columns[7].parse::<f32>().unwrap();
-                }
-                if columns.len() > 8 {
-                    end_g = columns[8].parse::<f32>().unwrap();
+        // An omitted end repeats the start
+        if values.len() == 1 {
+            values.push(values[0]);
+        }
+
+        // Segment times that do not fit an `i32` are reported on the end time
+        let overflow = || {
+            let text = columns.get(3).copied().unwrap_or_default();
+            ParseError::new(ParseErrorKind::InvalidNumber, 3, text)
+        };
+
+        let duration = end_time.checked_sub(start_time).ok_or_else(overflow)?;
+        values
+            .windows(2)
+            .enumerate()
+            .map(|(segment, values)| {
+                let offset = duration.checked_mul(segment as i32).ok_or_else(overflow)?;
+                let start_time = start_time.checked_add(offset).ok_or_else(overflow)?;
+                let end_time = end_time.checked_add(offset).ok_or_else(overflow)?;
+                let payload = Payload::from_values(ctype, values[0], values[1]);
+
+                Ok(Command::new(payload, easing, start_time, end_time))
+            })
+            .collect()
+    }
+}
+
+impl Payload {
+    /// Builds the payload of a value command from its value groups, see
+    /// `Command::parse_line`.
+    fn from_values(ctype: CommandType, start: [f32; 3], end: [f32; 3]) -> Payload {
+        match ctype {
+            CommandType::Movement => Payload::Move(
+                Vector2::<f32>::new(start[0], start[1]),
+                Vector2::<f32>::new(end[0], end[1]),
+            ),
+            CommandType::MovementX => Payload::MoveX(start[0], end[0]),
+            CommandType::MovementY => Payload::MoveY(start[0], end[0]),
+            CommandType::Fade => Payload::Fade(start[0], end[0]),
+            CommandType::Scale => Payload::Scale(start[0], end[0]),
+            CommandType::VectorScale => Payload::VectorScale(
+                Vector2::<f32>::new(start[0], start[1]),
+                Vector2::<f32>::new(end[0], end[1]),
+            ),
+            CommandType::Rotation => Payload::Rotation(start[0], end[0]),
+            CommandType::Colour => Payload::Colour(
+                Rgb::new(start[0], start[1], start[2]),
+                Rgb::new(end[0], end[1], end[2]),
+            ),
+
+            _ => unreachable!("{:?} has no values", ctype),
+        }
+    }
+}
+
+impl FromStr for Command {
+    type Err = ParseError;
+
+    /// Parses the first segment of a command line, see `Command::parse_line`.
+    fn from_str(data: &str) -> Result<Command, ParseError> {
+        Ok(Command::parse_line(data)?[0])
+    }
+}
+
+/// Writes the command in its most compact form, end time and end values are
+/// left out when they equal the start.
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self.payload {
+            Payload::Move(..) => "M",
+            Payload::MoveX(..) => "MX",
+            Payload::MoveY(..) => "MY",
+            Payload::Fade(..) => "F",
+            Payload::Scale(..) => "S",
+            Payload::VectorScale(..) => "V",
+            Payload::Rotation(..) => "R",
+            Payload::Colour(..) => "C",
+            Payload::Parameter(_) => "P",
+        };
+
+        write!(f, "{},{},{},", name, self.easing.to_id(), self.start_time)?;
+        if self.end_time != self.start_time {
+            write!(f, "{}", self.end_time)?;
+        }
+
+        match self.payload {
+            Payload::Move(start, end) | Payload::VectorScale(start, end) => {
+                write!(f, ",{},{}", start.x, start.y)?;
+                if end != start {
+                    write!(f, ",{},{}", end.x, end.y)?;
                 }
-                if columns.len() > 9 {
-                    end_b = columns[9].parse::<f32>().unwrap();
+            }
+            Payload::MoveX(start, end)
+            | Payload::MoveY(start, end)
+            | Payload::Fade(start, end)
+            | Payload::Scale(start, end)
+            | Payload::Rotation(start, end) => {
+                write!(f, ",{}", start)?;
+                if end != start {
+                    write!(f, ",{}", end)?;
                 }
-
-                Command {
-                    ctype: CommandType::VectorScale,
-                    easing,
-                    start_time,
-                    end_time,
-                    start_colour: Vector4::<f32>::new(start_r, start_g, start_b, 1.0),
-                    end_colour: Vector4::<f32>::new(end_r, end_g, end_b, 1.0),
-                    start_vector: Vector2::<f32>::new(0.0, 0.0),
-                    end_vector: Vector2::<f32>::new(0.0, 0.0),
-                    start_value: 0.0,
-                    end_value: 0.0,
+            }
+            Payload::Colour(start, end) => {
+                write!(f, ",{},{},{}", start.r, start.g, start.b)?;
+                if end != start {
+                    write!(f, ",{},{},{}", end.r, end.g, end.b)?;
                 }
             }
-            "P" => Command {
-                ctype: match columns[4] {
-                    "H" => CommandType::FlipHorizontal,
-                    "V" => CommandType::FlipVertical,
-                    "A" => CommandType::BlendingMode,
-
-                    _ => unimplemented!("Unimplemented Command Type {}", columns[4]),
-                },
-                easing,
-                start_time,
-                end_time,
-                start_colour: Vector4::<f32>::new(0.0, 0.0, 0.0, 1.0),
-                end_colour: Vector4::<f32>::new(0.0, 0.0, 0.0, 1.0),
-                start_vector: Vector2::<f32>::new(0.0, 0.0),
-                end_vector: Vector2::<f32>::new(0.0, 0.0),
-                start_value: 0.0,
-                end_value: 0.0,
-            },
-
-            _ => unimplemented!("Unknown CommandType: {}", columns[0]),
+            Payload::Parameter(kind) => write!(f, ",{}", kind)?,
         }
+
+        Ok(())
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct LoopCommand {
     pub start_time: i32,
     pub count: i32,
@@ -354,21 +351,120 @@ impl LoopCommand {
             commands: CommandGroup::new(),
         }
     }
+
+    /// osu! plays a loop at least once, even for a count of 0.
+    pub fn iterations(&self) -> i32 {
+        self.count.max(1)
+    }
+
+    /// Length of one iteration, from the first command start to the last
+    /// command end.
+    pub fn iteration_duration(&self) -> i32 {
+        match self.commands.time_range() {
+            Some((start_time, end_time)) => end_time.saturating_sub(start_time),
+            None => 0,
+        }
+    }
+
+    /// Absolute start and end time over all iterations, saturating at the
+    /// bounds of `i32`.
+    pub fn time_range(&self) -> Option<(i32, i32)> {
+        let (start_time, _) = self.commands.time_range()?;
+        let start_time = i64::from(self.start_time) + i64::from(start_time);
+        let duration = i64::from(self.iterations()) * i64::from(self.iteration_duration());
+
+        Some((saturate(start_time), saturate(start_time + duration)))
+    }
+
+    /// Expands every iteration into commands with absolute times, iteration
+    /// `n` is offset by `start_time + n * iteration_duration()`. Iterations
+    /// are produced as they are consumed, a loop may repeat millions of times.
+    pub fn unroll(&self) -> impl Iterator<Item = Command> + '_ {
+        let iteration = self.iteration();
+        let duration = i64::from(self.iteration_duration());
+        let length = iteration.len() as i64;
+
+        (0..length * i64::from(self.iterations())).map(move |index| {
+            let command = iteration[(index % length) as usize];
+            self.at_iteration(command, index / length, duration)
+        })
+    }
+
+    /// The commands of the loop that can drive a sprite at `time`: the first
+    /// iteration of every command and the last iteration of it that started by
+    /// `time`. They evaluate to the same state as the unrolled loop, without
+    /// expanding every iteration.
+    pub(crate) fn commands_at(&self, time: f64) -> Vec<Command> {
+        let duration = i64::from(self.iteration_duration());
+        let last = i64::from(self.iterations()) - 1;
+
+        let mut commands = Vec::<(i64, Command)>::new();
+        for command in self.iteration() {
+            commands.push((0, self.at_iteration(command, 0, duration)));
+
+            if duration > 0 {
+                let since = time - f64::from(self.start_time) - f64::from(command.start_time);
+                let index = ((since / duration as f64).floor() as i64).min(last);
+                if index > 0 {
+                    commands.push((index, self.at_iteration(command, index, duration)));
+                }
+            }
+        }
+
+        // Later iterations come last in the unrolled loop, they win ties
+        commands.sort_by_key(|(index, _)| *index);
+        commands.into_iter().map(|(_, command)| command).collect()
+    }
+
+    /// `command` moved into iteration `index`, times saturate at the bounds of
+    /// `i32`.
+    fn at_iteration(&self, mut command: Command, index: i64, duration: i64) -> Command {
+        let offset = i64::from(self.start_time) + index * duration;
+        let shift = |time: i32| saturate(i64::from(time) + offset);
+
+        command.start_time = shift(command.start_time);
+        command.end_time = shift(command.end_time);
+        command
+    }
+
+    /// The commands of one iteration relative to the loop start, nested
+    /// loops already unrolled.
+    pub(crate) fn iteration(&self) -> Vec<Command> {
+        let mut commands = self.commands.commands.clone();
+        for loop_command in &self.commands.loop_commands {
+            commands.extend(loop_command.unroll());
+        }
+
+        commands
+    }
+}
+
+impl fmt::Display for LoopCommand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "L,{},{}", self.start_time, self.count)
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct TriggerCommand {
-    pub name: String,
-    pub start_time: i32,
-    pub end_time: i32,
+    pub condition: TriggerCondition,
+    /// Start of the trigger window, unbounded when left out.
+    pub start_time: Option<i32>,
+    /// End of the trigger window, unbounded when left out.
+    pub end_time: Option<i32>,
     pub group_number: i32,
     pub commands: CommandGroup,
 }
 
 impl TriggerCommand {
-    pub fn new(name: String, start_time: i32, end_time: i32, group_number: i32) -> TriggerCommand {
+    pub fn new(
+        condition: TriggerCondition,
+        start_time: Option<i32>,
+        end_time: Option<i32>,
+        group_number: i32,
+    ) -> TriggerCommand {
         TriggerCommand {
-            name,
+            condition,
             start_time,
             end_time,
             group_number,
@@ -376,4 +472,73 @@ impl TriggerCommand {
             commands: CommandGroup::new(),
         }
     }
+
+    /// Whether a gameplay event at `time` can fire the trigger. A missing
+    /// start or end leaves that side of the window open, a trigger without a
+    /// window or with a `0,0` one is armed for the whole map.
+    pub fn is_armed_at(&self, time: i32) -> bool {
+        if let (Some(0), Some(0)) = (self.start_time, self.end_time) {
+            return true;
+        }
+
+        self.start_time.is_none_or(|start_time| start_time <= time)
+            && self.end_time.is_none_or(|end_time| time <= end_time)
+    }
+
+    /// How long the group plays once fired, its command times are relative
+    /// to the firing time.
+    pub fn duration(&self) -> i32 {
+        match self.commands.time_range() {
+            Some((_, end_time)) => end_time.max(0),
+            None => 0,
+        }
+    }
+
+    /// Whether the trigger window has both a start and an end. Open windows
+    /// and `0,0` ones can fire at any time of the map.
+    pub fn is_bounded(&self) -> bool {
+        match (self.start_time, self.end_time) {
+            (Some(0), Some(0)) => false,
+            (start_time, end_time) => start_time.is_some() && end_time.is_some(),
+        }
+    }
+
+    /// Earliest and latest time the group can play, firing at the start or
+    /// the end of the trigger window. `None` for triggers that are not
+    /// `is_bounded`, they have no time range to speak of.
+    pub fn time_range(&self) -> Option<(i32, i32)> {
+        if !self.is_bounded() {
+            return None;
+        }
+
+        let (start_time, end_time) = self.commands.time_range()?;
+        Some((
+            saturate(i64::from(self.start_time?) + i64::from(start_time)),
+            saturate(i64::from(self.end_time?) + i64::from(end_time)),
+        ))
+    }
+}
+
+impl fmt::Display for TriggerCommand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let time = |time: Option<i32>| time.map(|time| time.to_string()).unwrap_or_default();
+        let mut columns = vec![time(self.start_time), time(self.end_time)];
+        if self.group_number != 0 {
+            columns.push(self.group_number.to_string());
+        }
+        while columns.last().is_some_and(String::is_empty) {
+            columns.pop();
+        }
+
+        write!(f, "T,{}", self.condition)?;
+        for column in columns {
+            write!(f, ",{}", column)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) fn saturate(time: i64) -> i32 {
+    time.clamp(i64::from(i32::MIN), i64::from(i32::MAX)) as i32
 }
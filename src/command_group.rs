@@ -1,6 +1,8 @@
 use super::{Command, LoopCommand, TriggerCommand};
 
-#[derive(Debug)]
+use std::io::{self, Write};
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct CommandGroup {
     pub commands: Vec<Command>,
     pub loop_commands: Vec<LoopCommand>,
@@ -27,4 +29,63 @@ impl CommandGroup {
 
         &mut self.loop_commands.last_mut().unwrap().commands
     }
+
+    /// First start and last end time of every command in the group, loop
+    /// iterations and trigger windows included. Triggers that can fire at any
+    /// time are left out, check `TriggerCommand::is_bounded` for those.
+    pub fn time_range(&self) -> Option<(i32, i32)> {
+        let triggers = self
+            .trigger_commands
+            .iter()
+            .filter_map(|trigger_command| trigger_command.time_range());
+
+        span(self.command_range().into_iter().chain(triggers))
+    }
+
+    /// Like `time_range`, but without the trigger windows. Trigger groups
+    /// only play once fired, so this is what plays on its own.
+    pub(crate) fn command_range(&self) -> Option<(i32, i32)> {
+        let commands = self
+            .commands
+            .iter()
+            .map(|command| (command.start_time, command.end_time));
+        let loops = self
+            .loop_commands
+            .iter()
+            .filter_map(|loop_command| loop_command.time_range());
+
+        span(commands.chain(loops))
+    }
+
+    pub(crate) fn write_to<W: Write>(&self, writer: &mut W, depth: usize) -> io::Result<()> {
+        let indent = " ".repeat(depth);
+
+        for command in &self.commands {
+            write!(writer, "{}{}\r\n", indent, command)?;
+        }
+        for loop_command in &self.loop_commands {
+            write!(writer, "{}{}\r\n", indent, loop_command)?;
+            loop_command.commands.write_to(writer, depth + 1)?;
+        }
+        for trigger_command in &self.trigger_commands {
+            write!(writer, "{}{}\r\n", indent, trigger_command)?;
+            trigger_command.commands.write_to(writer, depth + 1)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for CommandGroup {
+    fn default() -> CommandGroup {
+        CommandGroup::new()
+    }
+}
+
+/// The smallest range covering every one of `ranges`.
+pub(crate) fn span<I: Iterator<Item = (i32, i32)>>(ranges: I) -> Option<(i32, i32)> {
+    ranges.fold(None, |range, (start_time, end_time)| match range {
+        Some((start, end)) => Some((start_time.min(start), end_time.max(end))),
+        None => Some((start_time, end_time)),
+    })
 }
@@ -0,0 +1,253 @@
+use std::f64::consts::PI;
+
+const ELASTIC_CONST: f64 = 2.0 * PI / 0.3;
+const ELASTIC_CONST2: f64 = 0.3 / 4.0;
+const BACK_CONST: f64 = 1.70158;
+const BACK_CONST2: f64 = BACK_CONST * 1.525;
+const BOUNCE_CONST: f64 = 1.0 / 2.75;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Easing {
+    None,
+    Out,
+    In,
+    InQuad,
+    OutQuad,
+    InOutQuad,
+    InCubic,
+    OutCubic,
+    InOutCubic,
+    InQuart,
+    OutQuart,
+    InOutQuart,
+    InQuint,
+    OutQuint,
+    InOutQuint,
+    InSine,
+    OutSine,
+    InOutSine,
+    InExpo,
+    OutExpo,
+    InOutExpo,
+    InCirc,
+    OutCirc,
+    InOutCirc,
+    InElastic,
+    OutElastic,
+    OutElasticHalf,
+    OutElasticQuarter,
+    InOutElastic,
+    InBack,
+    OutBack,
+    InOutBack,
+    InBounce,
+    OutBounce,
+    InOutBounce,
+}
+
+const EASINGS: [Easing; 35] = [
+    Easing::None,
+    Easing::Out,
+    Easing::In,
+    Easing::InQuad,
+    Easing::OutQuad,
+    Easing::InOutQuad,
+    Easing::InCubic,
+    Easing::OutCubic,
+    Easing::InOutCubic,
+    Easing::InQuart,
+    Easing::OutQuart,
+    Easing::InOutQuart,
+    Easing::InQuint,
+    Easing::OutQuint,
+    Easing::InOutQuint,
+    Easing::InSine,
+    Easing::OutSine,
+    Easing::InOutSine,
+    Easing::InExpo,
+    Easing::OutExpo,
+    Easing::InOutExpo,
+    Easing::InCirc,
+    Easing::OutCirc,
+    Easing::InOutCirc,
+    Easing::InElastic,
+    Easing::OutElastic,
+    Easing::OutElasticHalf,
+    Easing::OutElasticQuarter,
+    Easing::InOutElastic,
+    Easing::InBack,
+    Easing::OutBack,
+    Easing::InOutBack,
+    Easing::InBounce,
+    Easing::OutBounce,
+    Easing::InOutBounce,
+];
+
+impl Easing {
+    /// Maps the easing column of a command, `None` for unknown ids.
+    pub fn from_id(id: i32) -> Option<Easing> {
+        if id < 0 {
+            return None;
+        }
+
+        EASINGS.get(id as usize).copied()
+    }
+
+    pub fn to_id(self) -> i32 {
+        self as i32
+    }
+
+    /// Maps the linear progress `t` in `0.0..=1.0` onto the curve, using the
+    /// same formulas as osu!-stable. `Out` and `In` are the legacy names of
+    /// `OutQuad` and `InQuad`.
+    pub fn apply(self, t: f64) -> f64 {
+        match self {
+            Easing::None => t,
+
+            Easing::In | Easing::InQuad => t * t,
+            Easing::Out | Easing::OutQuad => t * (2.0 - t),
+            Easing::InOutQuad => {
+                if t < 0.5 {
+                    t * t * 2.0
+                } else {
+                    let t = t - 1.0;
+                    t * t * -2.0 + 1.0
+                }
+            }
+
+            Easing::InCubic => t * t * t,
+            Easing::OutCubic => {
+                let t = t - 1.0;
+                t * t * t + 1.0
+            }
+            Easing::InOutCubic => {
+                if t < 0.5 {
+                    t * t * t * 4.0
+                } else {
+                    let t = t - 1.0;
+                    t * t * t * 4.0 + 1.0
+                }
+            }
+
+            Easing::InQuart => t * t * t * t,
+            Easing::OutQuart => {
+                let t = t - 1.0;
+                1.0 - t * t * t * t
+            }
+            Easing::InOutQuart => {
+                if t < 0.5 {
+                    t * t * t * t * 8.0
+                } else {
+                    let t = t - 1.0;
+                    t * t * t * t * -8.0 + 1.0
+                }
+            }
+
+            Easing::InQuint => t * t * t * t * t,
+            Easing::OutQuint => {
+                let t = t - 1.0;
+                t * t * t * t * t + 1.0
+            }
+            Easing::InOutQuint => {
+                if t < 0.5 {
+                    t * t * t * t * t * 16.0
+                } else {
+                    let t = t - 1.0;
+                    t * t * t * t * t * 16.0 + 1.0
+                }
+            }
+
+            Easing::InSine => 1.0 - (t * PI * 0.5).cos(),
+            Easing::OutSine => (t * PI * 0.5).sin(),
+            Easing::InOutSine => 0.5 - 0.5 * (PI * t).cos(),
+
+            Easing::InExpo => 2f64.powf(10.0 * (t - 1.0)),
+            Easing::OutExpo => -(2f64.powf(-10.0 * t)) + 1.0,
+            Easing::InOutExpo => {
+                if t < 0.5 {
+                    0.5 * 2f64.powf(20.0 * t - 10.0)
+                } else {
+                    1.0 - 0.5 * 2f64.powf(-20.0 * t + 10.0)
+                }
+            }
+
+            Easing::InCirc => 1.0 - (1.0 - t * t).sqrt(),
+            Easing::OutCirc => {
+                let t = t - 1.0;
+                (1.0 - t * t).sqrt()
+            }
+            Easing::InOutCirc => {
+                let t = t * 2.0;
+                if t < 1.0 {
+                    0.5 - 0.5 * (1.0 - t * t).sqrt()
+                } else {
+                    let t = t - 2.0;
+                    0.5 + 0.5 * (1.0 - t * t).sqrt()
+                }
+            }
+
+            Easing::InElastic => {
+                -(2f64.powf(-10.0 + 10.0 * t)) * ((1.0 - ELASTIC_CONST2 - t) * ELASTIC_CONST).sin()
+            }
+            Easing::OutElastic => {
+                2f64.powf(-10.0 * t) * ((t - ELASTIC_CONST2) * ELASTIC_CONST).sin() + 1.0
+            }
+            Easing::OutElasticHalf => {
+                2f64.powf(-10.0 * t) * ((0.5 * t - ELASTIC_CONST2) * ELASTIC_CONST).sin() + 1.0
+            }
+            Easing::OutElasticQuarter => {
+                2f64.powf(-10.0 * t) * ((0.25 * t - ELASTIC_CONST2) * ELASTIC_CONST).sin() + 1.0
+            }
+            Easing::InOutElastic => {
+                let t = t * 2.0;
+                if t < 1.0 {
+                    -0.5 * 2f64.powf(-10.0 + 10.0 * t)
+                        * ((1.0 - ELASTIC_CONST2 * 1.5 - t) * ELASTIC_CONST / 1.5).sin()
+                } else {
+                    let t = t - 1.0;
+                    0.5 * 2f64.powf(-10.0 * t)
+                        * ((t - ELASTIC_CONST2 * 1.5) * ELASTIC_CONST / 1.5).sin()
+                        + 1.0
+                }
+            }
+
+            Easing::InBack => t * t * ((BACK_CONST + 1.0) * t - BACK_CONST),
+            Easing::OutBack => {
+                let t = t - 1.0;
+                t * t * ((BACK_CONST + 1.0) * t + BACK_CONST) + 1.0
+            }
+            Easing::InOutBack => {
+                let t = t * 2.0;
+                if t < 1.0 {
+                    0.5 * t * t * ((BACK_CONST2 + 1.0) * t - BACK_CONST2)
+                } else {
+                    let t = t - 2.0;
+                    0.5 * (t * t * ((BACK_CONST2 + 1.0) * t + BACK_CONST2) + 2.0)
+                }
+            }
+
+            Easing::InBounce => 1.0 - Easing::OutBounce.apply(1.0 - t),
+            Easing::OutBounce => {
+                if t < BOUNCE_CONST {
+                    7.5625 * t * t
+                } else if t < 2.0 * BOUNCE_CONST {
+                    let t = t - 1.5 * BOUNCE_CONST;
+                    7.5625 * t * t + 0.75
+                } else if t < 2.5 * BOUNCE_CONST {
+                    let t = t - 2.25 * BOUNCE_CONST;
+                    7.5625 * t * t + 0.9375
+                } else {
+                    let t = t - 2.625 * BOUNCE_CONST;
+                    7.5625 * t * t + 0.984375
+                }
+            }
+            Easing::InOutBounce => {
+                if t < 0.5 {
+                    0.5 - 0.5 * Easing::OutBounce.apply(1.0 - t * 2.0)
+                } else {
+                    Easing::OutBounce.apply((t - 0.5) * 2.0) * 0.5 + 0.5
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    InvalidNumber,
+    MissingColumn,
+    UnknownCommand,
+    UnknownEventType,
+    OrphanCommand,
+    InvalidUtf8,
+    Io,
+}
+
+impl ParseErrorKind {
+    fn description(self) -> &'static str {
+        match self {
+            ParseErrorKind::InvalidNumber => "invalid number",
+            ParseErrorKind::MissingColumn => "missing column",
+            ParseErrorKind::UnknownCommand => "unknown command",
+            ParseErrorKind::UnknownEventType => "unknown event type",
+            ParseErrorKind::OrphanCommand => "command without an event",
+            ParseErrorKind::InvalidUtf8 => "invalid UTF-8",
+            ParseErrorKind::Io => "failed to read",
+        }
+    }
+}
+
+/// An error raised while parsing a storyboard line.
+///
+/// `line` is 1-based and `0` when the error did not come from a whole
+/// storyboard, `column` is the index of the comma separated field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+}
+
+impl ParseError {
+    pub fn new(kind: ParseErrorKind, column: usize, text: &str) -> ParseError {
+        ParseError {
+            kind,
+            line: 0,
+            column,
+            text: text.to_string(),
+        }
+    }
+
+    pub(crate) fn at_line(mut self, line: usize) -> ParseError {
+        self.line = line;
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {} `{}`",
+            self.line,
+            self.column,
+            self.kind.description(),
+            self.text
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem found while parsing leniently, the offending line was skipped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl From<ParseError> for Diagnostic {
+    fn from(err: ParseError) -> Diagnostic {
+        let severity = match err.kind {
+            ParseErrorKind::UnknownEventType | ParseErrorKind::OrphanCommand => Severity::Warning,
+
+            _ => Severity::Error,
+        };
+
+        Diagnostic {
+            line: err.line,
+            severity,
+            message: format!(
+                "{} `{}` in column {}",
+                err.kind.description(),
+                err.text,
+                err.column
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}: {}", self.line, self.severity, self.message)
+    }
+}
+
+pub(crate) fn column<'a>(columns: &[&'a str], index: usize) -> Result<&'a str, ParseError> {
+    match columns.get(index) {
+        Some(column) => Ok(column),
+        None => Err(ParseError::new(
+            ParseErrorKind::MissingColumn,
+            index,
+            &columns.join(","),
+        )),
+    }
+}
+
+pub(crate) fn number<T: FromStr>(columns: &[&str], index: usize) -> Result<T, ParseError> {
+    let text = column(columns, index)?;
+
+    text.trim()
+        .parse::<T>()
+        .map_err(|_| ParseError::new(ParseErrorKind::InvalidNumber, index, text))
+}
+
+/// Like `number`, but `None` for a missing or empty column.
+pub(crate) fn optional_number<T: FromStr>(
+    columns: &[&str],
+    index: usize,
+) -> Result<Option<T>, ParseError> {
+    match columns.get(index) {
+        Some(text) if !text.trim().is_empty() => number(columns, index).map(Some),
+
+        _ => Ok(None),
+    }
+}
@@ -1,8 +1,19 @@
-use super::Command;
+use super::command::saturate;
+use super::error::{column, number};
+use super::fade::Fades;
+use super::{
+    evaluate_triggers, Command, CommandGroup, GameplayEvent, ParseError, ParseErrorKind,
+    SpriteState, TriggerFiring,
+};
 
-use cgmath::{Vector2};
+use cgmath::Vector2;
+use std::borrow::Cow;
+use std::fmt;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-#[derive(PartialEq, Debug)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum EventType {
     Sprite,
     Animation,
@@ -10,7 +21,7 @@ pub enum EventType {
     Unknown,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum LayerType {
     Background,
     Fail,
@@ -22,7 +33,7 @@ pub enum LayerType {
     Unknown,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum OriginType {
     TopLeft,
     TopCentre,
@@ -37,13 +48,13 @@ pub enum OriginType {
     Custom,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum LoopType {
     LoopForever,
     LoopOnce,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Event {
     pub etype: EventType,
     pub layer: LayerType,
@@ -51,7 +62,7 @@ pub struct Event {
     pub file_path: String,
     pub pos: Vector2<f32>,
     pub origin: OriginType,
-    pub commands: Vec<Command>,
+    pub commands: CommandGroup,
 
     /* Event: Animation */
     pub frame_count: i32,
@@ -71,7 +82,7 @@ impl Event {
             file_path: String::default(),
             pos: Vector2::<f32>::new(0.0, 0.0),
             origin: OriginType::TopLeft,
-            commands: Vec::<Command>::new(),
+            commands: CommandGroup::new(),
             frame_count: 0,
             frame_delay: 0.0,
             loop_type: LoopType::LoopOnce,
@@ -80,99 +91,329 @@ impl Event {
         }
     }
 
-    pub fn from_string(data: String) -> Event {
-        let columns: Vec<&str> = data.split(",").collect();
+    /// Evaluates position, scale, rotation, opacity, colour and parameters of
+    /// the sprite at `time`, in milliseconds. Loops are applied, trigger
+    /// groups are not.
+    pub fn state_at(&self, time: f64) -> SpriteState {
+        SpriteState::evaluate(self.pos, &self.commands_at(time), time)
+    }
 
-        let event_type = match columns[0] {
-            "Sprite" => EventType::Sprite,
-            "Animation" => EventType::Animation,
-            "Sample" => EventType::Sample,
+    /// When the event starts, the sample time for samples.
+    pub fn start_time(&self) -> Option<i32> {
+        self.time_range().map(|(start_time, _)| start_time)
+    }
+
+    pub fn end_time(&self) -> Option<i32> {
+        self.time_range().map(|(_, end_time)| end_time)
+    }
+
+    /// First start and last end time over every command, loop iteration and
+    /// trigger window, `None` for sprites without commands.
+    pub fn time_range(&self) -> Option<(i32, i32)> {
+        match self.etype {
+            EventType::Sample => Some((self.time, self.time)),
+            EventType::Unknown => None,
 
-            _ => EventType::Unknown,
+            _ => self.commands.time_range(),
+        }
+    }
+
+    /// Like `time_range`, but without the trigger windows: when the event
+    /// plays unless one of its triggers fires.
+    pub fn command_range(&self) -> Option<(i32, i32)> {
+        match self.etype {
+            EventType::Sample => Some((self.time, self.time)),
+            EventType::Unknown => None,
+
+            _ => self.commands.command_range(),
+        }
+    }
+
+    /// Like `time_range`, but trimmed to the part where the fades leave the
+    /// sprite with an opacity above zero. `None` if it is never visible.
+    pub fn visible_range(&self) -> Option<(i32, i32)> {
+        if self.etype == EventType::Sample {
+            return None;
+        }
+
+        let (start_time, end_time) = self.time_range()?;
+
+        let fades = Fades::new(self);
+        let first = match fades.first() {
+            Some(first) => first,
+            None => return Some((start_time, end_time)),
+        };
+
+        let start_time = i64::from(start_time);
+        let end_time = i64::from(end_time);
+        let mut visible: Option<(i64, i64)> = None;
+        let mut show = |start: i64, end: i64| {
+            visible = match visible {
+                Some((first, last)) => Some((first.min(start), last.max(end))),
+                None => Some((start, end)),
+            };
         };
 
-        let event_layer = match columns[1] {
-            "Background" => LayerType::Background,
-            "Fail" => LayerType::Fail,
-            "Pass" => LayerType::Pass,
-            "Foreground" => LayerType::Foreground,
-            "Overlay" => LayerType::Overlay,
+        if first.opacity.0 > 0.0 {
+            show(start_time, first.start_time);
+        }
+
+        // Only the first and last visible moment matter, so the loop
+        // iterations in between are never looked at
+        for fade in fades.bounds() {
+            // A fade drives the opacity until the next one starts
+            let next = fades.after(&fade).map_or(end_time, |next| next.start_time);
+
+            let (start, end) = fade.opacity;
+            if start > 0.0 || end > 0.0 {
+                show(fade.start_time, fade.end_time.min(next));
+            }
+            if end > 0.0 && fade.end_time < next {
+                show(fade.end_time, next);
+            }
+        }
+
+        let (first, last) = visible?;
+        Some((
+            saturate(first.max(start_time)),
+            saturate(last.min(end_time)),
+        ))
+    }
+
+    /// The animation frame shown at `time` and its image, `sb/anim.png` turns
+    /// into `sb/anim0.png`, `sb/anim1.png` and so on. Frame 0 starts with the
+    /// first command or loop, trigger windows do not count. `None` for
+    /// anything but animations.
+    pub fn frame_at(&self, time: f64) -> Option<(usize, PathBuf)> {
+        if self.etype != EventType::Animation || self.frame_count <= 0 {
+            return None;
+        }
+
+        let start_time = self.command_range().map_or(0, |(start_time, _)| start_time);
+        let start_time = f64::from(start_time);
+        let mut frame = 0;
+        if time > start_time && self.frame_delay > 0.0 {
+            frame = ((time - start_time) / self.frame_delay) as usize;
+        }
+
+        let frame_count = self.frame_count as usize;
+        let frame = match self.loop_type {
+            LoopType::LoopForever => frame % frame_count,
+            LoopType::LoopOnce => frame.min(frame_count - 1),
+        };
+
+        Some((frame, PathBuf::from(self.frame_path(frame))))
+    }
+
+    pub(crate) fn frame_path(&self, frame: usize) -> String {
+        let name_start = self.file_path.rfind(&['/', '\\'][..]);
+        let extension = self
+            .file_path
+            .rfind('.')
+            .filter(|&dot| name_start.is_none_or(|name_start| dot > name_start));
+
+        match extension {
+            Some(dot) => format!(
+                "{}{}{}",
+                &self.file_path[..dot],
+                frame,
+                &self.file_path[dot..]
+            ),
+            None => format!("{}{}", self.file_path, frame),
+        }
+    }
+
+    /// Works out which of the event's trigger groups fire for the gameplay
+    /// `events`, see `evaluate_triggers`.
+    pub fn trigger_firings(&self, events: &[GameplayEvent]) -> Vec<TriggerFiring> {
+        evaluate_triggers(&self.commands.trigger_commands, events)
+    }
+
+    /// Replaces every loop with its unrolled commands.
+    pub fn flatten_loops(&mut self) {
+        let loops = mem::take(&mut self.commands.loop_commands);
+        for loop_command in &loops {
+            self.commands.commands.extend(loop_command.unroll());
+        }
+    }
+
+    /// The plain commands together with the loop iterations that can drive
+    /// the sprite at `time`, see `LoopCommand::commands_at`.
+    fn commands_at(&self, time: f64) -> Cow<'_, [Command]> {
+        if self.commands.loop_commands.is_empty() {
+            return Cow::Borrowed(&self.commands.commands);
+        }
+
+        let mut commands = self.commands.commands.clone();
+        for loop_command in &self.commands.loop_commands {
+            commands.extend(loop_command.commands_at(time));
+        }
+
+        Cow::Owned(commands)
+    }
+
+    fn parse_layer(layer: &str) -> LayerType {
+        match layer {
+            "0" | "Background" => LayerType::Background,
+            "1" | "Fail" => LayerType::Fail,
+            "2" | "Pass" => LayerType::Pass,
+            "3" | "Foreground" => LayerType::Foreground,
+            "4" | "Overlay" => LayerType::Overlay,
             "Samples" => LayerType::Samples,
 
             _ => LayerType::Unknown,
+        }
+    }
+
+    /// Unknown or malformed events turn into an empty `Event`, use
+    /// `str::parse` to find out what went wrong.
+    pub fn from_string(data: String) -> Event {
+        data.parse::<Event>().unwrap_or_default()
+    }
+}
+
+impl Default for Event {
+    fn default() -> Event {
+        Event::new()
+    }
+}
+
+impl FromStr for Event {
+    type Err = ParseError;
+
+    fn from_str(data: &str) -> Result<Event, ParseError> {
+        let columns: Vec<&str> = data.split(',').collect();
+
+        let event_type = match columns[0] {
+            "4" | "Sprite" => EventType::Sprite,
+            "5" | "Sample" => EventType::Sample,
+            "6" | "Animation" => EventType::Animation,
+
+            etype => return Err(ParseError::new(ParseErrorKind::UnknownEventType, 0, etype)),
         };
 
-        let event_origin = match columns[2] {
-            "TopLeft" => OriginType::TopLeft,
-            "TopCentre" => OriginType::TopCentre,
-            "TopRight" => OriginType::TopRight,
-            "CentreLeft" => OriginType::CentreLeft,
-            "Centre" => OriginType::Centre,
-            "CentreRight" => OriginType::CentreRight,
-            "BottomLeft" => OriginType::BottomLeft,
-            "BottomCentre" => OriginType::BottomCentre,
-            "BottomRight" => OriginType::BottomRight,
+        if event_type == EventType::Sample {
+            let mut event = Event::new();
+            event.etype = event_type;
+            event.time = number::<i32>(&columns, 1)?;
+            event.layer = Event::parse_layer(column(&columns, 2)?);
+            event.file_path = column(&columns, 3)?.trim_matches('"').to_string();
+            event.volume = 100;
+            if columns.len() > 4 {
+                event.volume = number::<i32>(&columns, 4)?;
+            }
+
+            return Ok(event);
+        }
+
+        let event_layer = Event::parse_layer(column(&columns, 1)?);
+
+        let event_origin = match column(&columns, 2)? {
+            "0" | "TopLeft" => OriginType::TopLeft,
+            "1" | "Centre" => OriginType::Centre,
+            "2" | "CentreLeft" => OriginType::CentreLeft,
+            "3" | "TopRight" => OriginType::TopRight,
+            "4" | "BottomCentre" => OriginType::BottomCentre,
+            "5" | "TopCentre" => OriginType::TopCentre,
+            "7" | "CentreRight" => OriginType::CentreRight,
+            "8" | "BottomLeft" => OriginType::BottomLeft,
+            "9" | "BottomRight" => OriginType::BottomRight,
 
             _ => OriginType::Custom,
         };
 
-        let file_path = columns[3].trim_matches('"');
-
-        let position_x = columns[4].parse::<f32>().unwrap(); // TODO: test for errors
-        let position_y = columns[5].parse::<f32>().unwrap(); // TODO: here to.
-
-        let position = Vector2::<f32>::new(position_x, position_y);
-        match event_type {
-            EventType::Sprite => {
-                return Event {
-                    etype: event_type,
-                    layer: event_layer,
-
-                    /* Event: Shared */
-                    file_path: file_path.to_string(),
-                    pos: position,
-                    origin: event_origin,
-                    commands: Vec::<Command>::new(),
-                    /* Event: Animation */
-                    frame_count: 0,
-                    frame_delay: 0.0,
-                    loop_type: LoopType::LoopOnce,
-                    /* Event: Sample */
-                    time: 0,
-                    volume: 0,
-                };
-            }
-            EventType::Animation => {
-                let mut loop_type = LoopType::LoopForever;
-                if columns.len() > 8 {
-                    loop_type = match columns[8] {
-                        "LoopOnce" => LoopType::LoopOnce,
-
-                        _ => LoopType::LoopForever,
-                    }
+        let file_path = column(&columns, 3)?.trim_matches('"');
+
+        let position_x = number::<f32>(&columns, 4)?;
+        let position_y = number::<f32>(&columns, 5)?;
+
+        let mut event = Event::new();
+        event.etype = event_type;
+        event.layer = event_layer;
+        event.file_path = file_path.to_string();
+        event.pos = Vector2::<f32>::new(position_x, position_y);
+        event.origin = event_origin;
+
+        if event.etype == EventType::Animation {
+            event.frame_count = number::<i32>(&columns, 6)?;
+            event.frame_delay = number::<f64>(&columns, 7)?;
+            event.loop_type = LoopType::LoopForever;
+            if columns.len() > 8 {
+                event.loop_type = match columns[8] {
+                    "1" | "LoopOnce" => LoopType::LoopOnce,
+
+                    _ => LoopType::LoopForever,
                 }
+            }
+        }
+
+        Ok(event)
+    }
+}
+
+impl fmt::Display for LayerType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl fmt::Display for OriginType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl fmt::Display for LoopType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// Writes the event line, without its commands.
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.etype {
+            EventType::Sprite => write!(
+                f,
+                "Sprite,{},{},\"{}\",{},{}",
+                self.layer, self.origin, self.file_path, self.pos.x, self.pos.y
+            ),
+            EventType::Animation => write!(
+                f,
+                "Animation,{},{},\"{}\",{},{},{},{},{}",
+                self.layer,
+                self.origin,
+                self.file_path,
+                self.pos.x,
+                self.pos.y,
+                self.frame_count,
+                self.frame_delay,
+                self.loop_type
+            ),
+            EventType::Sample => {
+                let layer = match self.layer {
+                    LayerType::Background => "0".to_string(),
+                    LayerType::Fail => "1".to_string(),
+                    LayerType::Pass => "2".to_string(),
+                    LayerType::Foreground => "3".to_string(),
 
-                return Event {
-                    etype: event_type,
-                    layer: event_layer,
-                    file_path: file_path.to_string(),
-                    pos: position,
-                    origin: event_origin,
-                    commands: Vec::<Command>::new(),
-
-                    /* Event: Animation */
-                    frame_count: columns[6].parse::<i32>().unwrap(),
-                    frame_delay: columns[7].parse::<f64>().unwrap(),
-                    loop_type: loop_type,
-                    /* Event: Sample */
-                    time: 0,
-                    volume: 0,
+                    layer => layer.to_string(),
                 };
-            }
 
-            _ => println!("Unimplemented Storyboard Event! {:#?}", event_type),
-        };
+                write!(
+                    f,
+                    "Sample,{},{},\"{}\",{}",
+                    self.time, layer, self.file_path, self.volume
+                )
+            }
 
-        Event::new() // Return empty event if not implemented
+            EventType::Unknown => Ok(()),
+        }
     }
 }
+
+/// Where the storyboard file `file` is found inside `folder`.
+pub(crate) fn asset_path(folder: &Path, file: &str) -> PathBuf {
+    // Storyboards are written on Windows more often than not
+    folder.join(file.replace('\\', "/"))
+}
@@ -0,0 +1,146 @@
+use super::{Command, Event, Payload};
+
+/// A fade of an event. `order` is its position among the unrolled commands,
+/// the group (0 for plain commands, then one per loop), the iteration and the
+/// index within it; fades starting at the same time keep that order.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Fade {
+    pub start_time: i64,
+    pub end_time: i64,
+    pub opacity: (f32, f32),
+    order: (usize, i64, usize),
+}
+
+impl Fade {
+    fn new(command: &Command, order: (usize, i64, usize)) -> Option<Fade> {
+        match command.payload {
+            Payload::Fade(start, end) => Some(Fade {
+                start_time: i64::from(command.start_time),
+                end_time: i64::from(command.end_time),
+                opacity: (start, end),
+                order,
+            }),
+
+            _ => None,
+        }
+    }
+
+    fn key(&self) -> (i64, (usize, i64, usize)) {
+        (self.start_time, self.order)
+    }
+}
+
+/// A fade of a loop, repeated every `period` for `count` iterations.
+struct LoopFade {
+    first: Fade,
+    period: i64,
+    count: i64,
+}
+
+impl LoopFade {
+    fn at(&self, iteration: i64) -> Fade {
+        let offset = iteration * self.period;
+        let (group, _, index) = self.first.order;
+
+        Fade {
+            start_time: self.first.start_time + offset,
+            end_time: self.first.end_time + offset,
+            opacity: self.first.opacity,
+            order: (group, iteration, index),
+        }
+    }
+
+    /// The first iteration that comes after `fade`. Iterations are in order,
+    /// so this is a binary search.
+    fn after(&self, fade: &Fade) -> Option<Fade> {
+        let (mut low, mut high) = (0, self.count);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            match self.at(middle).key() > fade.key() {
+                true => high = middle,
+                false => low = middle + 1,
+            }
+        }
+
+        (low < self.count).then(|| self.at(low))
+    }
+}
+
+/// The fades of an event in the order osu! plays them. Loops are kept as a
+/// single iteration and a period, a loop may repeat millions of times.
+pub(crate) struct Fades {
+    plain: Vec<Fade>,
+    loops: Vec<LoopFade>,
+}
+
+impl Fades {
+    pub fn new(event: &Event) -> Fades {
+        let mut plain: Vec<Fade> = event
+            .commands
+            .commands
+            .iter()
+            .enumerate()
+            .filter_map(|(index, command)| Fade::new(command, (0, 0, index)))
+            .collect();
+        plain.sort_by_key(Fade::key);
+
+        let mut loops = Vec::<LoopFade>::new();
+        for (group, loop_command) in event.commands.loop_commands.iter().enumerate() {
+            let offset = i64::from(loop_command.start_time);
+            let period = i64::from(loop_command.iteration_duration());
+            let count = i64::from(loop_command.iterations());
+
+            for (index, command) in loop_command.iteration().iter().enumerate() {
+                if let Some(mut first) = Fade::new(command, (group + 1, 0, index)) {
+                    first.start_time += offset;
+                    first.end_time += offset;
+                    loops.push(LoopFade {
+                        first,
+                        period,
+                        count,
+                    });
+                }
+            }
+        }
+
+        Fades { plain, loops }
+    }
+
+    pub fn first(&self) -> Option<Fade> {
+        self.plain
+            .first()
+            .copied()
+            .into_iter()
+            .chain(self.loops.iter().map(|fade| fade.at(0)))
+            .min_by_key(Fade::key)
+    }
+
+    /// The fade played right after `fade`.
+    pub fn after(&self, fade: &Fade) -> Option<Fade> {
+        let next = self
+            .plain
+            .partition_point(|plain| plain.key() <= fade.key());
+
+        self.plain
+            .get(next)
+            .copied()
+            .into_iter()
+            .chain(
+                self.loops
+                    .iter()
+                    .filter_map(|loop_fade| loop_fade.after(fade)),
+            )
+            .min_by_key(Fade::key)
+    }
+
+    /// The fades that can start or end a visible stretch earliest or latest:
+    /// every plain fade and the first and last iteration of every loop fade.
+    /// Later iterations of a fade start, end and hand over later.
+    pub fn bounds(&self) -> impl Iterator<Item = Fade> + '_ {
+        self.plain.iter().copied().chain(
+            self.loops
+                .iter()
+                .flat_map(|fade| vec![fade.at(0), fade.at(fade.count - 1)]),
+        )
+    }
+}
@@ -1,10 +1,41 @@
+mod beatmap;
+mod colour;
 mod command;
 mod command_group;
+mod easing;
+mod error;
 mod event;
+mod fade;
+mod lint;
+mod load;
+mod parameter;
+mod parser;
+mod reader;
+#[cfg(feature = "render")]
+mod render;
+mod state;
 mod storyboard;
+mod trigger;
+mod variable;
 
-pub use command::{Command, CommandType, Easing, LoopCommand, TriggerCommand};
+pub use beatmap::{BackgroundEvent, BreakEvent, VideoEvent};
+pub use colour::Rgb;
+pub use command::{Command, CommandType, LoopCommand, Payload, TriggerCommand};
+pub use easing::Easing;
+pub use error::{Diagnostic, ParseError, ParseErrorKind, Severity};
 pub use event::{Event, EventType, LayerType, LoopType, OriginType};
+pub use lint::{lint, Lint, LintKind};
+pub use load::{LoadProfile, LoadSample};
+pub use parameter::{Parameter, ParameterKind};
+pub use reader::StoryboardReader;
+#[cfg(feature = "render")]
+pub use render::{render_frame, render_sequence, Renderer};
+pub use state::SpriteState;
 pub use storyboard::Storyboard;
+pub use trigger::{
+    evaluate_triggers, GameplayEvent, GameplayEventKind, HitSound, HitSoundAddition, SampleSet,
+    TriggerCondition, TriggerFiring,
+};
+pub use variable::Variable;
 
 pub use command_group::CommandGroup;
@@ -0,0 +1,259 @@
+use super::event::asset_path;
+use super::{
+    Command, CommandType, Event, EventType, LayerType, Severity, Storyboard, TriggerCondition,
+};
+
+use std::fmt;
+use std::path::Path;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LintKind {
+    EndBeforeStart,
+    OverlappingCommands,
+    NeverVisible,
+    MissingFile,
+    ZeroLengthLoop,
+    UnknownTrigger,
+    WrongLayer,
+}
+
+/// A problem with an event of a parsed storyboard, `event` indexes
+/// `Storyboard::events`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    pub kind: LintKind,
+    pub event: usize,
+    pub message: String,
+}
+
+impl LintKind {
+    /// Stable kebab-case name, meant for machine readable output.
+    pub fn name(self) -> &'static str {
+        match self {
+            LintKind::EndBeforeStart => "end-before-start",
+            LintKind::OverlappingCommands => "overlapping-commands",
+            LintKind::NeverVisible => "never-visible",
+            LintKind::MissingFile => "missing-file",
+            LintKind::ZeroLengthLoop => "zero-length-loop",
+            LintKind::UnknownTrigger => "unknown-trigger",
+            LintKind::WrongLayer => "wrong-layer",
+        }
+    }
+
+    /// Problems that break the storyboard in game are errors, the ones that
+    /// only waste resources are warnings.
+    pub fn severity(self) -> Severity {
+        match self {
+            LintKind::OverlappingCommands | LintKind::NeverVisible | LintKind::UnknownTrigger => {
+                Severity::Warning
+            }
+
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl Lint {
+    fn new(kind: LintKind, event: usize, message: String) -> Lint {
+        Lint {
+            kind,
+            event,
+            message,
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.kind.severity()
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "event {}: {}: {} [{}]",
+            self.event,
+            self.severity(),
+            self.message,
+            self.kind.name()
+        )
+    }
+}
+
+/// Checks `storyboard` for the problems osu!'s ranking criteria care about.
+/// Referenced files are looked up relative to `folder`, that check is skipped
+/// without one.
+pub fn lint(storyboard: &Storyboard, folder: Option<&Path>) -> Vec<Lint> {
+    let mut lints = Vec::<Lint>::new();
+
+    for (index, event) in storyboard.events.iter().enumerate() {
+        lint_layer(&mut lints, index, event);
+        lint_commands(&mut lints, index, event);
+
+        if let Some(folder) = folder {
+            lint_files(&mut lints, index, event, folder);
+        }
+    }
+
+    lints
+}
+
+fn lint_layer(lints: &mut Vec<Lint>, index: usize, event: &Event) {
+    let allowed = match event.etype {
+        EventType::Sprite | EventType::Animation => {
+            event.layer != LayerType::Samples && event.layer != LayerType::Unknown
+        }
+        EventType::Sample => matches!(
+            event.layer,
+            LayerType::Background | LayerType::Fail | LayerType::Pass | LayerType::Foreground
+        ),
+
+        EventType::Unknown => true,
+    };
+
+    if !allowed {
+        lints.push(Lint::new(
+            LintKind::WrongLayer,
+            index,
+            format!(
+                "{:?} can not be placed on the {} layer",
+                event.etype, event.layer
+            ),
+        ));
+    }
+}
+
+fn lint_commands(lints: &mut Vec<Lint>, index: usize, event: &Event) {
+    let groups = event
+        .commands
+        .loop_commands
+        .iter()
+        .map(|loop_command| &loop_command.commands)
+        .chain(
+            event
+                .commands
+                .trigger_commands
+                .iter()
+                .map(|trigger| &trigger.commands),
+        );
+
+    for command in event
+        .commands
+        .commands
+        .iter()
+        .chain(groups.flat_map(|group| group.commands.iter()))
+    {
+        if command.end_time < command.start_time {
+            lints.push(Lint::new(
+                LintKind::EndBeforeStart,
+                index,
+                format!("`{}` ends before it starts", command),
+            ));
+        }
+    }
+
+    for loop_command in &event.commands.loop_commands {
+        if loop_command.iteration_duration() <= 0 {
+            lints.push(Lint::new(
+                LintKind::ZeroLengthLoop,
+                index,
+                format!("`{}` has iterations without length", loop_command),
+            ));
+        }
+    }
+
+    for trigger in &event.commands.trigger_commands {
+        if let TriggerCondition::Unknown(name) = &trigger.condition {
+            lints.push(Lint::new(
+                LintKind::UnknownTrigger,
+                index,
+                format!("trigger `{}` is never fired by osu!", name),
+            ));
+        }
+    }
+
+    if event.etype != EventType::Sprite && event.etype != EventType::Animation {
+        return;
+    }
+
+    // Every iteration of a loop is the same, one of them and the seam to the
+    // next are enough to find its overlaps
+    let mut commands = event.commands.commands.clone();
+    for loop_command in &event.commands.loop_commands {
+        let length = loop_command.iteration().len();
+        commands.extend(loop_command.unroll().take(2 * length));
+    }
+
+    for ctype in OVERLAPPING_TYPES.iter() {
+        if let Some((first, second)) = overlap(&commands, *ctype) {
+            lints.push(Lint::new(
+                LintKind::OverlappingCommands,
+                index,
+                format!("`{}` overlaps `{}`", second, first),
+            ));
+        }
+    }
+
+    if event.visible_range().is_none() {
+        lints.push(Lint::new(
+            LintKind::NeverVisible,
+            index,
+            format!("`{}` is never visible", event.file_path),
+        ));
+    }
+}
+
+/// Parameters may overlap freely, they only switch something on.
+const OVERLAPPING_TYPES: [CommandType; 8] = [
+    CommandType::Movement,
+    CommandType::MovementX,
+    CommandType::MovementY,
+    CommandType::Fade,
+    CommandType::Scale,
+    CommandType::VectorScale,
+    CommandType::Rotation,
+    CommandType::Colour,
+];
+
+/// The first command of `ctype` that starts before an earlier one ended.
+fn overlap(commands: &[Command], ctype: CommandType) -> Option<(&Command, &Command)> {
+    let mut commands: Vec<&Command> = commands
+        .iter()
+        .filter(|command| command.ctype() == ctype)
+        .collect();
+    commands.sort_by_key(|command| command.start_time);
+
+    let mut latest: Option<&Command> = None;
+    for command in commands {
+        match latest {
+            Some(previous) if command.start_time < previous.end_time => {
+                return Some((previous, command));
+            }
+
+            _ => latest = Some(command),
+        }
+    }
+
+    None
+}
+
+fn lint_files(lints: &mut Vec<Lint>, index: usize, event: &Event, folder: &Path) {
+    let files = match event.etype {
+        EventType::Sprite | EventType::Sample => vec![event.file_path.clone()],
+        EventType::Animation => (0..event.frame_count.max(0) as usize)
+            .map(|frame| event.frame_path(frame))
+            .collect(),
+
+        EventType::Unknown => return,
+    };
+
+    for file in files {
+        if !asset_path(folder, &file).is_file() {
+            lints.push(Lint::new(
+                LintKind::MissingFile,
+                index,
+                format!("`{}` does not exist", file),
+            ));
+        }
+    }
+}
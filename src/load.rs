@@ -0,0 +1,211 @@
+use super::{Event, EventType, LayerType, Storyboard};
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+/// Area of the 640x480 playfield the load is measured against.
+const PLAYFIELD_AREA: f64 = 640.0 * 480.0;
+
+/// The storyboard load at a point in time, `1.0` means the visible sprites
+/// cover the playfield exactly once.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LoadSample {
+    pub time: i32,
+    pub load: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadProfile {
+    /// One sample at every command boundary, in time order.
+    pub samples: Vec<LoadSample>,
+    /// The first of the highest samples.
+    pub peak: Option<LoadSample>,
+}
+
+impl LoadProfile {
+    /// Samples with a load above `threshold`.
+    pub fn above(&self, threshold: f64) -> impl Iterator<Item = &LoadSample> {
+        self.samples
+            .iter()
+            .filter(move |sample| sample.load > threshold)
+    }
+}
+
+struct Sprite<'a> {
+    event: &'a Event,
+    start_time: i32,
+    end_time: i32,
+}
+
+pub(crate) fn load_profile<F>(storyboard: &Storyboard, mut dimensions: F) -> LoadProfile
+where
+    F: FnMut(&Path) -> Option<(u32, u32)>,
+{
+    let mut sprites: Vec<Sprite> = storyboard
+        .events
+        .iter()
+        .filter(|event| counts_towards_load(event))
+        .filter_map(|event| {
+            // Trigger groups only play once fired, their windows do not count
+            let (start_time, end_time) = event.command_range()?;
+
+            Some(Sprite {
+                event,
+                start_time,
+                end_time,
+            })
+        })
+        .collect();
+    sprites.sort_by_key(|sprite| sprite.start_time);
+
+    let mut times = Vec::<i32>::new();
+    for sprite in &sprites {
+        let commands = &sprite.event.commands;
+        let loops = commands
+            .loop_commands
+            .iter()
+            .flat_map(|loop_command| loop_command.unroll());
+
+        for command in commands.commands.iter().copied().chain(loops) {
+            times.push(command.start_time);
+            times.push(command.end_time);
+        }
+    }
+    times.sort_unstable();
+    times.dedup();
+
+    let mut sizes = HashMap::<PathBuf, Option<(u32, u32)>>::new();
+    let mut samples = Vec::<LoadSample>::with_capacity(times.len());
+    let mut active = Vec::<&Sprite>::new();
+    let mut next = 0;
+
+    for time in times {
+        while next < sprites.len() && sprites[next].start_time <= time {
+            active.push(&sprites[next]);
+            next += 1;
+        }
+        active.retain(|sprite| sprite.end_time >= time);
+
+        let mut load = 0.0;
+        for sprite in &active {
+            let path = match sprite.event.frame_at(f64::from(time)) {
+                Some((_, path)) => path,
+                None => PathBuf::from(&sprite.event.file_path),
+            };
+            let size = *sizes
+                .entry(path)
+                .or_insert_with_key(|path| dimensions(path));
+
+            let (width, height) = match size {
+                Some(size) => size,
+                None => continue,
+            };
+
+            let state = sprite.event.state_at(f64::from(time));
+            if state.opacity <= 0.0 {
+                continue;
+            }
+
+            load += f64::from(width)
+                * f64::from(state.scale.x.abs())
+                * f64::from(height)
+                * f64::from(state.scale.y.abs())
+                / PLAYFIELD_AREA;
+        }
+
+        samples.push(LoadSample { time, load });
+    }
+
+    let peak = samples
+        .iter()
+        .fold(None, |peak: Option<LoadSample>, sample| match peak {
+            Some(peak) if peak.load >= sample.load => Some(peak),
+
+            _ => Some(*sample),
+        });
+
+    LoadProfile { samples, peak }
+}
+
+/// The Fail layer only shows instead of the Pass layer, so it is left out.
+fn counts_towards_load(event: &Event) -> bool {
+    match event.etype {
+        EventType::Sprite | EventType::Animation => matches!(
+            event.layer,
+            LayerType::Background | LayerType::Pass | LayerType::Foreground | LayerType::Overlay
+        ),
+
+        _ => false,
+    }
+}
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// Reads the size of a PNG or JPEG image from its header, without decoding
+/// the image. `None` for other formats and unreadable files.
+pub(crate) fn image_dimensions(path: &Path) -> Option<(u32, u32)> {
+    let mut reader = BufReader::new(File::open(path).ok()?);
+
+    let mut magic = [0u8; 2];
+    reader.read_exact(&mut magic).ok()?;
+    match magic {
+        [0x89, b'P'] => png_dimensions(&mut reader),
+        [0xff, 0xd8] => jpeg_dimensions(&mut reader),
+
+        _ => None,
+    }
+}
+
+/// The size is stored in the `IHDR` chunk, which always comes first.
+fn png_dimensions<R: Read>(reader: &mut R) -> Option<(u32, u32)> {
+    // Rest of the signature, chunk length, chunk type, width and height
+    let mut header = [0u8; 22];
+    reader.read_exact(&mut header).ok()?;
+    if header[..6] != PNG_SIGNATURE[2..] || &header[10..14] != b"IHDR" {
+        return None;
+    }
+
+    let number = |bytes: &[u8]| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    Some((number(&header[14..18]), number(&header[18..22])))
+}
+
+/// Walks the segments up to the first start of frame, which holds the size.
+fn jpeg_dimensions<R: Read>(reader: &mut R) -> Option<(u32, u32)> {
+    let mut byte = [0u8; 1];
+
+    loop {
+        reader.read_exact(&mut byte).ok()?;
+        if byte[0] != 0xff {
+            continue;
+        }
+
+        // Markers may be padded with any number of 0xff bytes
+        while byte[0] == 0xff {
+            reader.read_exact(&mut byte).ok()?;
+        }
+
+        let marker = byte[0];
+        if let 0x00 | 0x01 | 0xd0..=0xd9 = marker {
+            continue;
+        }
+
+        let mut length = [0u8; 2];
+        reader.read_exact(&mut length).ok()?;
+        let length = u16::from_be_bytes(length);
+
+        // Every SOFn except DHT, JPG and DAC, which share the range
+        if let 0xc0..=0xc3 | 0xc5..=0xc7 | 0xc9..=0xcb | 0xcd..=0xcf = marker {
+            let mut frame = [0u8; 5];
+            reader.read_exact(&mut frame).ok()?;
+
+            let height = u16::from_be_bytes([frame[1], frame[2]]);
+            let width = u16::from_be_bytes([frame[3], frame[4]]);
+            return Some((u32::from(width), u32::from(height)));
+        }
+
+        let skip = u64::from(length.saturating_sub(2));
+        io::copy(&mut reader.by_ref().take(skip), &mut io::sink()).ok()?;
+    }
+}
@@ -0,0 +1,56 @@
+use std::fmt;
+
+/// What a `P` command switches on, written as `H`, `V` or `A`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ParameterKind {
+    FlipHorizontal,
+    FlipVertical,
+    AdditiveBlending,
+}
+
+/// A `P` command. Unlike the other commands parameters do not interpolate,
+/// they hold while active and the sprite falls back to normal afterwards.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Parameter {
+    pub kind: ParameterKind,
+    pub start_time: i32,
+    pub end_time: i32,
+}
+
+impl ParameterKind {
+    pub fn from_name(name: &str) -> Option<ParameterKind> {
+        match name {
+            "H" => Some(ParameterKind::FlipHorizontal),
+            "V" => Some(ParameterKind::FlipVertical),
+            "A" => Some(ParameterKind::AdditiveBlending),
+
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ParameterKind::FlipHorizontal => "H",
+            ParameterKind::FlipVertical => "V",
+            ParameterKind::AdditiveBlending => "A",
+        }
+    }
+}
+
+impl Parameter {
+    /// Active from the start to the end time inclusive. osu! treats a
+    /// parameter without duration as permanent, it stays active from its start
+    /// time onwards. One that ends before it starts is never active.
+    pub fn is_active_at(&self, time: f64) -> bool {
+        let start_time = f64::from(self.start_time);
+        let end_time = f64::from(self.end_time);
+
+        start_time <= time && (end_time == start_time || time <= end_time)
+    }
+}
+
+impl fmt::Display for ParameterKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
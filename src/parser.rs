@@ -0,0 +1,249 @@
+use super::error::{column, number, optional_number};
+use super::{
+    BackgroundEvent, BreakEvent, Command, CommandGroup, Diagnostic, Event, LoopCommand, ParseError,
+    ParseErrorKind, TriggerCommand, TriggerCondition, Variable, VideoEvent,
+};
+
+enum Section {
+    Events,
+    Variables,
+    Other,
+}
+
+enum Target {
+    Root,
+    Loop,
+    Trigger,
+    /// The loop or trigger header failed to parse, its commands are skipped.
+    Skipped,
+}
+
+/// Line based storyboard parser, events are handed out once the next event
+/// starts or the input is finished.
+///
+/// In lenient mode broken lines are skipped and recorded as diagnostics,
+/// together with the commands of an event that failed to parse.
+pub(crate) struct Parser {
+    pub line: usize,
+    section: Section,
+    pub variables: Vec<Variable>,
+    pub background: Option<BackgroundEvent>,
+    pub video: Option<VideoEvent>,
+    pub breaks: Vec<BreakEvent>,
+
+    event: Option<Event>,
+    target: Target,
+    skipping: bool,
+
+    lenient: bool,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Parser {
+    pub fn new(lenient: bool) -> Parser {
+        Parser {
+            line: 0,
+            section: Section::Events,
+            variables: Vec::<Variable>::new(),
+            background: None,
+            video: None,
+            breaks: Vec::<BreakEvent>::new(),
+
+            event: None,
+            target: Target::Root,
+            skipping: false,
+
+            lenient,
+            diagnostics: Vec::<Diagnostic>::new(),
+        }
+    }
+
+    /// Only reads the `[Events]` section, skipping the beatmap header and
+    /// every other section.
+    pub fn for_beatmap(mut self) -> Parser {
+        self.section = Section::Other;
+        self
+    }
+
+    pub fn line(&mut self, data: &str) -> Result<Option<Event>, ParseError> {
+        self.line += 1;
+
+        let data = match self.line {
+            1 => data.trim_start_matches('\u{feff}'),
+            _ => data,
+        };
+
+        match self.parse_line(data) {
+            Ok(event) => Ok(event),
+            Err(err) => self.report(err).map(|_| None),
+        }
+    }
+
+    /// Skips a line that could not be read, it still counts as a line.
+    pub fn skip(&mut self, err: ParseError) -> Result<(), ParseError> {
+        self.line += 1;
+        self.report(err)
+    }
+
+    pub fn finish(&mut self) -> Option<Event> {
+        self.target = Target::Root;
+        self.event.take()
+    }
+
+    fn parse_line(&mut self, data: &str) -> Result<Option<Event>, ParseError> {
+        if data.starts_with("//") || data.trim().is_empty() {
+            return Ok(None);
+        }
+
+        let header = data.trim();
+        if header.starts_with('[') && header.ends_with(']') {
+            self.section = match header {
+                "[Events]" => Section::Events,
+                "[Variables]" => Section::Variables,
+
+                _ => Section::Other,
+            };
+
+            return Ok(None);
+        }
+
+        match self.section {
+            Section::Events => {}
+            Section::Variables => {
+                if let Some(variable) = Variable::from_line(data) {
+                    self.set_variable(variable);
+                }
+
+                return Ok(None);
+            }
+            Section::Other => return Ok(None),
+        }
+
+        let data = Variable::expand(&self.variables, data);
+        let data: &str = &data;
+
+        let mut cmd_raw = data;
+        let mut depth = 0;
+
+        while cmd_raw.starts_with(&[' ', '_', '\t'][..]) {
+            depth += 1;
+            cmd_raw = &cmd_raw[1..];
+        }
+
+        if depth == 0 {
+            let etype = data.split(',').next().unwrap_or_default();
+            if let "0" | "Background" | "1" | "Video" | "2" | "Break" | "3" | "Colour" = etype {
+                let finished = self.finish();
+                self.skipping = false;
+                self.parse_beatmap_event(etype, data)?;
+
+                return Ok(finished);
+            }
+
+            let event = data.parse::<Event>();
+            let finished = self.finish();
+
+            self.skipping = event.is_err();
+            match event {
+                Ok(event) => self.event = Some(event),
+                Err(err) => self.report(err)?,
+            }
+
+            return Ok(finished);
+        }
+
+        let event = match self.event.as_mut() {
+            Some(event) => event,
+            None if self.skipping => return Ok(None),
+            None => return Err(ParseError::new(ParseErrorKind::OrphanCommand, 0, cmd_raw)),
+        };
+
+        if depth < 2 {
+            self.target = Target::Root;
+        } else if let Target::Skipped = self.target {
+            return Ok(None);
+        }
+
+        let columns: Vec<&str> = cmd_raw.split(',').collect();
+        if let "T" | "L" = columns[0] {
+            self.target = Target::Skipped;
+        }
+
+        match columns[0] {
+            "T" => {
+                let name = column(&columns, 1)?;
+                let start_time = optional_number::<i32>(&columns, 2)?;
+                let end_time = optional_number::<i32>(&columns, 3)?;
+                let group_number = optional_number::<i32>(&columns, 4)?.unwrap_or(0);
+
+                event.commands.add_trigger(TriggerCommand::new(
+                    TriggerCondition::from_name(name),
+                    start_time,
+                    end_time,
+                    group_number,
+                ));
+                self.target = Target::Trigger;
+            }
+
+            "L" => {
+                let start_time = number::<i32>(&columns, 1)?;
+                let count = number::<i32>(&columns, 2)?;
+
+                event.commands.add_loop(LoopCommand::new(start_time, count));
+                self.target = Target::Loop;
+            }
+            _ => {
+                let commands = Command::parse_line(cmd_raw)?;
+                self.group().commands.extend(commands);
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn parse_beatmap_event(&mut self, etype: &str, data: &str) -> Result<(), ParseError> {
+        match etype {
+            "0" | "Background" => self.background = Some(data.parse::<BackgroundEvent>()?),
+            "1" | "Video" => self.video = Some(data.parse::<VideoEvent>()?),
+            "2" | "Break" => self.breaks.push(data.parse::<BreakEvent>()?),
+
+            _ => {} // Legacy background colour changes, not drawn by osu! anymore
+        }
+
+        Ok(())
+    }
+
+    fn set_variable(&mut self, variable: Variable) {
+        match self.variables.iter_mut().find(|v| v.name == variable.name) {
+            Some(existing) => existing.value = variable.value,
+            None => self.variables.push(variable),
+        }
+    }
+
+    fn report(&mut self, err: ParseError) -> Result<(), ParseError> {
+        let err = err.at_line(self.line);
+        if !self.lenient {
+            return Err(err);
+        }
+
+        self.diagnostics.push(Diagnostic::from(err));
+        Ok(())
+    }
+
+    fn group(&mut self) -> &mut CommandGroup {
+        let commands = &mut self.event.as_mut().unwrap().commands;
+
+        match self.target {
+            Target::Root => commands,
+            Target::Loop => &mut commands.loop_commands.last_mut().unwrap().commands,
+            Target::Trigger => &mut commands.trigger_commands.last_mut().unwrap().commands,
+            Target::Skipped => unreachable!("commands of a broken group are skipped"),
+        }
+    }
+}
+
+/// Splits `data` into lines ending in `\n`, `\r\n` or a lone `\r`.
+pub(crate) fn lines(data: &str) -> impl Iterator<Item = &str> {
+    data.split('\n')
+        .flat_map(|line| line.strip_suffix('\r').unwrap_or(line).split('\r'))
+}
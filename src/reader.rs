@@ -0,0 +1,144 @@
+use super::parser::Parser;
+use super::{
+    BackgroundEvent, BreakEvent, Diagnostic, Event, ParseError, ParseErrorKind, Variable,
+    VideoEvent,
+};
+
+use std::io::{self, BufRead};
+use std::str;
+
+/// Reads a storyboard one event at a time, so only the event being parsed
+/// and a single line are kept in memory.
+///
+/// Everything that is not an event (variables, background, video and break
+/// periods) is kept on the reader and available once it has been read.
+pub struct StoryboardReader<R: BufRead> {
+    reader: R,
+    parser: Parser,
+    buffer: Vec<u8>,
+    after_cr: bool,
+    done: bool,
+}
+
+impl<R: BufRead> StoryboardReader<R> {
+    /// Fails on the first malformed line.
+    pub fn new(reader: R) -> StoryboardReader<R> {
+        StoryboardReader::with_parser(reader, Parser::new(false))
+    }
+
+    /// Skips malformed lines, they are collected in `diagnostics`.
+    pub fn lenient(reader: R) -> StoryboardReader<R> {
+        StoryboardReader::with_parser(reader, Parser::new(true))
+    }
+
+    fn with_parser(reader: R, parser: Parser) -> StoryboardReader<R> {
+        StoryboardReader {
+            reader,
+            parser,
+            buffer: Vec::<u8>::new(),
+            after_cr: false,
+            done: false,
+        }
+    }
+
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.parser.diagnostics
+    }
+
+    pub fn variables(&self) -> &[Variable] {
+        &self.parser.variables
+    }
+
+    pub fn background(&self) -> Option<&BackgroundEvent> {
+        self.parser.background.as_ref()
+    }
+
+    pub fn video(&self) -> Option<&VideoEvent> {
+        self.parser.video.as_ref()
+    }
+
+    pub fn breaks(&self) -> &[BreakEvent] {
+        &self.parser.breaks
+    }
+
+    /// Reads the next line into `buffer`, lines may end in `\n`, `\r\n` or a
+    /// lone `\r`. Returns `false` at the end of the input.
+    fn read_line(&mut self) -> io::Result<bool> {
+        self.buffer.clear();
+
+        if self.after_cr {
+            self.after_cr = false;
+            if self.reader.fill_buf()?.first() == Some(&b'\n') {
+                self.reader.consume(1);
+            }
+        }
+
+        let mut read = false;
+        loop {
+            let available = self.reader.fill_buf()?;
+            if available.is_empty() {
+                return Ok(read);
+            }
+            read = true;
+
+            match available.iter().position(|&b| b == b'\n' || b == b'\r') {
+                Some(end) => {
+                    self.after_cr = available[end] == b'\r';
+                    self.buffer.extend_from_slice(&available[..end]);
+                    self.reader.consume(end + 1);
+
+                    return Ok(true);
+                }
+                None => {
+                    let length = available.len();
+                    self.buffer.extend_from_slice(available);
+                    self.reader.consume(length);
+                }
+            }
+        }
+    }
+
+    fn next_event(&mut self) -> Result<Option<Event>, ParseError> {
+        loop {
+            let read = self.read_line().map_err(|err| {
+                ParseError::new(ParseErrorKind::Io, 0, &err.to_string())
+                    .at_line(self.parser.line + 1)
+            })?;
+            if !read {
+                return Ok(self.parser.finish());
+            }
+
+            let line = match str::from_utf8(&self.buffer) {
+                Ok(line) => line,
+                Err(_) => {
+                    let text = String::from_utf8_lossy(&self.buffer);
+                    self.parser
+                        .skip(ParseError::new(ParseErrorKind::InvalidUtf8, 0, &text))?;
+                    continue;
+                }
+            };
+
+            if let Some(event) = self.parser.line(line)? {
+                return Ok(Some(event));
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for StoryboardReader<R> {
+    type Item = Result<Event, ParseError>;
+
+    fn next(&mut self) -> Option<Result<Event, ParseError>> {
+        if self.done {
+            return None;
+        }
+
+        let event = self.next_event();
+        match event {
+            Ok(Some(_)) => {}
+            _ => self.done = true,
+        }
+
+        event.transpose()
+    }
+}
@@ -0,0 +1,338 @@
+use super::event::asset_path;
+use super::{Event, EventType, LayerType, OriginType, SpriteState, Storyboard};
+
+use cgmath::Vector2;
+use image::{ImageError, Rgba, RgbaImage};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const PLAYFIELD_WIDTH: u32 = 640;
+pub const WIDESCREEN_WIDTH: u32 = 854;
+pub const PLAYFIELD_HEIGHT: u32 = 480;
+
+/// Composites storyboard frames on the CPU. Images are loaded from the assets
+/// directory once and kept for following frames.
+pub struct Renderer {
+    assets: PathBuf,
+    widescreen: bool,
+    failing: bool,
+    layer: Option<LayerType>,
+    images: HashMap<PathBuf, Option<RgbaImage>>,
+}
+
+impl Renderer {
+    pub fn new<P: Into<PathBuf>>(assets: P) -> Renderer {
+        Renderer {
+            assets: assets.into(),
+            widescreen: false,
+            failing: false,
+            layer: None,
+            images: HashMap::<PathBuf, Option<RgbaImage>>::new(),
+        }
+    }
+
+    /// Renders 854x480 instead of 640x480, the playfield stays centred.
+    pub fn widescreen(mut self, widescreen: bool) -> Renderer {
+        self.widescreen = widescreen;
+        self
+    }
+
+    /// Draws the Fail layer instead of the Pass layer.
+    pub fn failing(mut self, failing: bool) -> Renderer {
+        self.failing = failing;
+        self
+    }
+
+    /// Draws nothing but `layer`.
+    pub fn layer(mut self, layer: LayerType) -> Renderer {
+        self.layer = Some(layer);
+        self
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        match self.widescreen {
+            true => (WIDESCREEN_WIDTH, PLAYFIELD_HEIGHT),
+            false => (PLAYFIELD_WIDTH, PLAYFIELD_HEIGHT),
+        }
+    }
+
+    /// Renders every layer at `time`, in milliseconds.
+    pub fn render(&mut self, storyboard: &Storyboard, time: f64) -> RgbaImage {
+        if let Some(layer) = self.layer {
+            return self.render_layers(storyboard, time, &[layer]);
+        }
+
+        let state = match self.failing {
+            true => LayerType::Fail,
+            false => LayerType::Pass,
+        };
+
+        self.render_layers(
+            storyboard,
+            time,
+            &[
+                LayerType::Background,
+                state,
+                LayerType::Foreground,
+                LayerType::Overlay,
+            ],
+        )
+    }
+
+    /// Renders `layers` at `time`, drawn in the given order.
+    pub fn render_layers(
+        &mut self,
+        storyboard: &Storyboard,
+        time: f64,
+        layers: &[LayerType],
+    ) -> RgbaImage {
+        let (width, height) = self.size();
+        let mut canvas = Canvas {
+            width,
+            height,
+            x_offset: (width - PLAYFIELD_WIDTH) as f32 / 2.0,
+            pixels: vec![[0.0; 3]; (width * height) as usize],
+        };
+
+        for layer in layers {
+            for event in storyboard
+                .events
+                .iter()
+                .filter(|event| event.layer == *layer)
+            {
+                self.draw(&mut canvas, event, time);
+            }
+        }
+
+        canvas.into_image()
+    }
+
+    /// Renders the frames from `start` up to `end` into `out_dir` as
+    /// `000000.png`, `000001.png`, ... and returns the number of frames written.
+    pub fn render_sequence<P: AsRef<Path>>(
+        &mut self,
+        storyboard: &Storyboard,
+        start: f64,
+        end: f64,
+        fps: f64,
+        out_dir: P,
+    ) -> Result<usize, ImageError> {
+        let out_dir = out_dir.as_ref();
+        fs::create_dir_all(out_dir).map_err(ImageError::IoError)?;
+
+        let frames = match end > start && fps > 0.0 {
+            true => ((end - start) / 1000.0 * fps).ceil() as usize,
+            false => 0,
+        };
+
+        for frame in 0..frames {
+            let time = start + frame as f64 * 1000.0 / fps;
+            self.render(storyboard, time)
+                .save(out_dir.join(format!("{:06}.png", frame)))?;
+        }
+
+        Ok(frames)
+    }
+
+    fn draw(&mut self, canvas: &mut Canvas, event: &Event, time: f64) {
+        let path = match event.etype {
+            EventType::Sprite => PathBuf::from(&event.file_path),
+            EventType::Animation => match event.frame_at(time) {
+                Some((_, path)) => path,
+                None => return,
+            },
+
+            _ => return,
+        };
+
+        match event.command_range() {
+            Some((start_time, end_time))
+                if f64::from(start_time) <= time && time <= f64::from(end_time) => {}
+
+            _ => return,
+        }
+
+        let state = event.state_at(time);
+        if state.opacity <= 0.0 || state.scale.x == 0.0 || state.scale.y == 0.0 {
+            return;
+        }
+
+        if let Some(image) = self.image(&path) {
+            canvas.draw(image, event.origin, &state);
+        }
+    }
+
+    fn image(&mut self, path: &Path) -> Option<&RgbaImage> {
+        let assets = &self.assets;
+
+        self.images
+            .entry(path.to_path_buf())
+            .or_insert_with(|| {
+                image::open(asset_path(assets, &path.to_string_lossy()))
+                    .ok()
+                    .map(|image| image.to_rgba8())
+            })
+            .as_ref()
+    }
+}
+
+/// Renders a single frame of every layer, see `Renderer`.
+pub fn render_frame<P: Into<PathBuf>>(
+    storyboard: &Storyboard,
+    assets: P,
+    time: f64,
+    widescreen: bool,
+) -> RgbaImage {
+    Renderer::new(assets)
+        .widescreen(widescreen)
+        .render(storyboard, time)
+}
+
+/// Renders every layer from `start` up to `end` at `fps` into `out_dir`, see
+/// `Renderer::render_sequence`.
+pub fn render_sequence<P: Into<PathBuf>, Q: AsRef<Path>>(
+    storyboard: &Storyboard,
+    assets: P,
+    start: f64,
+    end: f64,
+    fps: f64,
+    out_dir: Q,
+) -> Result<usize, ImageError> {
+    Renderer::new(assets).render_sequence(storyboard, start, end, fps, out_dir)
+}
+
+fn anchor(origin: OriginType) -> Vector2<f32> {
+    match origin {
+        OriginType::TopLeft | OriginType::Custom => Vector2::<f32>::new(0.0, 0.0),
+        OriginType::TopCentre => Vector2::<f32>::new(0.5, 0.0),
+        OriginType::TopRight => Vector2::<f32>::new(1.0, 0.0),
+        OriginType::CentreLeft => Vector2::<f32>::new(0.0, 0.5),
+        OriginType::Centre => Vector2::<f32>::new(0.5, 0.5),
+        OriginType::CentreRight => Vector2::<f32>::new(1.0, 0.5),
+        OriginType::BottomLeft => Vector2::<f32>::new(0.0, 1.0),
+        OriginType::BottomCentre => Vector2::<f32>::new(0.5, 1.0),
+        OriginType::BottomRight => Vector2::<f32>::new(1.0, 1.0),
+    }
+}
+
+struct Canvas {
+    width: u32,
+    height: u32,
+    x_offset: f32,
+    pixels: Vec<[f32; 3]>,
+}
+
+impl Canvas {
+    fn draw(&mut self, image: &RgbaImage, origin: OriginType, state: &SpriteState) {
+        let size = Vector2::<f32>::new(image.width() as f32, image.height() as f32);
+        let anchor = anchor(origin);
+        let origin = Vector2::<f32>::new(anchor.x * size.x, anchor.y * size.y);
+        let position = Vector2::<f32>::new(state.position.x + self.x_offset, state.position.y);
+        let (sin, cos) = state.rotation.sin_cos();
+        let tint = state.colour.to_srgb();
+
+        // Screen space bounds of the transformed quad
+        let corners = [(0.0, 0.0), (size.x, 0.0), (0.0, size.y), (size.x, size.y)];
+        let (mut min_x, mut min_y) = (f32::MAX, f32::MAX);
+        let (mut max_x, mut max_y) = (f32::MIN, f32::MIN);
+        for (x, y) in corners.iter() {
+            let dx = (x - origin.x) * state.scale.x;
+            let dy = (y - origin.y) * state.scale.y;
+            let x = position.x + dx * cos - dy * sin;
+            let y = position.y + dx * sin + dy * cos;
+
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+        }
+
+        let start_x = min_x.floor().max(0.0) as u32;
+        let start_y = min_y.floor().max(0.0) as u32;
+        let end_x = (max_x.ceil().max(0.0) as u32).min(self.width);
+        let end_y = (max_y.ceil().max(0.0) as u32).min(self.height);
+
+        for y in start_y..end_y {
+            for x in start_x..end_x {
+                let sx = x as f32 + 0.5 - position.x;
+                let sy = y as f32 + 0.5 - position.y;
+
+                let mut u = (sx * cos + sy * sin) / state.scale.x + origin.x;
+                let mut v = (sy * cos - sx * sin) / state.scale.y + origin.y;
+                if u < 0.0 || v < 0.0 || u >= size.x || v >= size.y {
+                    continue;
+                }
+
+                if state.flip_horizontal {
+                    u = size.x - u;
+                }
+                if state.flip_vertical {
+                    v = size.y - v;
+                }
+
+                let texel = sample(image, u, v);
+                let alpha = texel[3] * state.opacity;
+                let colour = [texel[0] * tint.x, texel[1] * tint.y, texel[2] * tint.z];
+
+                let pixel = &mut self.pixels[(y * self.width + x) as usize];
+                for channel in 0..3 {
+                    pixel[channel] = match state.additive {
+                        true => pixel[channel] + colour[channel] * alpha,
+                        false => pixel[channel] * (1.0 - alpha) + colour[channel] * alpha,
+                    };
+                }
+            }
+        }
+    }
+
+    fn into_image(self) -> RgbaImage {
+        let mut image = RgbaImage::new(self.width, self.height);
+        for (pixel, colour) in image.pixels_mut().zip(self.pixels.iter()) {
+            let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+            *pixel = Rgba([
+                channel(colour[0]),
+                channel(colour[1]),
+                channel(colour[2]),
+                255,
+            ]);
+        }
+
+        image
+    }
+}
+
+/// Bilinear sample at the texel coordinate `u`, `v`, normalized to `0.0..=1.0`.
+fn sample(image: &RgbaImage, u: f32, v: f32) -> [f32; 4] {
+    let max_x = image.width() as i64 - 1;
+    let max_y = image.height() as i64 - 1;
+
+    let u = u - 0.5;
+    let v = v - 0.5;
+    let x0 = u.floor();
+    let y0 = v.floor();
+    let fx = u - x0;
+    let fy = v - y0;
+
+    let texel = |x: f32, y: f32| {
+        let x = (x as i64).clamp(0, max_x) as u32;
+        let y = (y as i64).clamp(0, max_y) as u32;
+        image.get_pixel(x, y).0
+    };
+
+    let samples = [
+        (texel(x0, y0), (1.0 - fx) * (1.0 - fy)),
+        (texel(x0 + 1.0, y0), fx * (1.0 - fy)),
+        (texel(x0, y0 + 1.0), (1.0 - fx) * fy),
+        (texel(x0 + 1.0, y0 + 1.0), fx * fy),
+    ];
+
+    let mut result = [0.0f32; 4];
+    for (texel, weight) in samples.iter() {
+        for channel in 0..4 {
+            result[channel] += f32::from(texel[channel]) / 255.0 * weight;
+        }
+    }
+
+    result
+}
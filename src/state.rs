@@ -0,0 +1,172 @@
+use super::{Command, ParameterKind, Payload, Rgb};
+
+use cgmath::{Vector2, VectorSpace};
+
+/// What a sprite looks like at a single point in time.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SpriteState {
+    pub position: Vector2<f32>,
+    pub scale: Vector2<f32>,
+    pub rotation: f32,
+    pub opacity: f32,
+    pub colour: Rgb,
+    /// Set while a `P,H` parameter is active, see `Parameter::is_active_at`.
+    pub flip_horizontal: bool,
+    /// Set while a `P,V` parameter is active.
+    pub flip_vertical: bool,
+    /// Set while a `P,A` parameter is active, the sprite is then added onto
+    /// what is below it instead of alpha blended.
+    pub additive: bool,
+}
+
+impl SpriteState {
+    /// Evaluates `commands` the way osu! does: every command type is driven by
+    /// the latest command that started at or before `time`, holding its end
+    /// value once it finished. Before the first command of a type its start
+    /// value is held. `M` drives both axes which `MX` and `MY` drive
+    /// individually, the `S` scale is multiplied with the `V` vector scale.
+    pub fn evaluate(position: Vector2<f32>, commands: &[Command], time: f64) -> SpriteState {
+        let x = evaluate(commands, time, position.x, |command| {
+            match command.payload {
+                Payload::Move(start, end) => Some((start.x, end.x)),
+                Payload::MoveX(start, end) => Some((start, end)),
+
+                _ => None,
+            }
+        });
+        let y = evaluate(commands, time, position.y, |command| {
+            match command.payload {
+                Payload::Move(start, end) => Some((start.y, end.y)),
+                Payload::MoveY(start, end) => Some((start, end)),
+
+                _ => None,
+            }
+        });
+
+        let scale = evaluate(commands, time, 1.0, |command| match command.payload {
+            Payload::Scale(start, end) => Some((start, end)),
+
+            _ => None,
+        });
+        let vector_scale =
+            evaluate(
+                commands,
+                time,
+                Vector2::<f32>::new(1.0, 1.0),
+                |command| match command.payload {
+                    Payload::VectorScale(start, end) => Some((start, end)),
+
+                    _ => None,
+                },
+            );
+
+        let rotation = evaluate(commands, time, 0.0, |command| match command.payload {
+            Payload::Rotation(start, end) => Some((start, end)),
+
+            _ => None,
+        });
+        let opacity = evaluate(commands, time, 1.0, |command| match command.payload {
+            Payload::Fade(start, end) => Some((start, end)),
+
+            _ => None,
+        });
+        let colour = evaluate(commands, time, Rgb::WHITE, |command| {
+            match command.payload {
+                Payload::Colour(start, end) => Some((start, end)),
+
+                _ => None,
+            }
+        });
+
+        SpriteState {
+            position: Vector2::<f32>::new(x, y),
+            scale: vector_scale * scale,
+            rotation,
+            opacity,
+            colour,
+            flip_horizontal: parameter(commands, time, ParameterKind::FlipHorizontal),
+            flip_vertical: parameter(commands, time, ParameterKind::FlipVertical),
+            additive: parameter(commands, time, ParameterKind::AdditiveBlending),
+        }
+    }
+}
+
+trait Interpolate: Copy {
+    fn interpolate(self, other: Self, amount: f32) -> Self;
+}
+
+impl Interpolate for f32 {
+    fn interpolate(self, other: f32, amount: f32) -> f32 {
+        self + (other - self) * amount
+    }
+}
+
+impl Interpolate for Vector2<f32> {
+    fn interpolate(self, other: Vector2<f32>, amount: f32) -> Vector2<f32> {
+        self.lerp(other, amount)
+    }
+}
+
+impl Interpolate for Rgb {
+    fn interpolate(self, other: Rgb, amount: f32) -> Rgb {
+        Rgb::new(
+            self.r.interpolate(other.r, amount),
+            self.g.interpolate(other.g, amount),
+            self.b.interpolate(other.b, amount),
+        )
+    }
+}
+
+fn evaluate<T, F>(commands: &[Command], time: f64, default: T, values: F) -> T
+where
+    T: Interpolate,
+    F: Fn(&Command) -> Option<(T, T)>,
+{
+    let mut first: Option<(&Command, (T, T))> = None;
+    let mut current: Option<(&Command, (T, T))> = None;
+
+    for command in commands {
+        let value = match values(command) {
+            Some(value) => value,
+            None => continue,
+        };
+
+        match first {
+            Some((earliest, _)) if earliest.start_time <= command.start_time => {}
+            _ => first = Some((command, value)),
+        }
+
+        if f64::from(command.start_time) <= time {
+            match current {
+                Some((latest, _)) if latest.start_time > command.start_time => {}
+                _ => current = Some((command, value)),
+            }
+        }
+    }
+
+    match (current, first) {
+        (Some((command, (start, end))), _) => start.interpolate(end, progress(command, time)),
+        (None, Some((_, (start, _)))) => start,
+        (None, None) => default,
+    }
+}
+
+fn progress(command: &Command, time: f64) -> f32 {
+    let start_time = f64::from(command.start_time);
+    let end_time = f64::from(command.end_time);
+    if end_time <= start_time {
+        return 1.0;
+    }
+
+    let t = ((time - start_time) / (end_time - start_time)).clamp(0.0, 1.0);
+    command.easing.apply(t) as f32
+}
+
+/// Overlapping parameters of the same kind do not cancel out, the flag is set
+/// as long as any of them is active.
+fn parameter(commands: &[Command], time: f64, kind: ParameterKind) -> bool {
+    commands
+        .iter()
+        .filter_map(Command::parameter)
+        .any(|parameter| parameter.kind == kind && parameter.is_active_at(time))
+}
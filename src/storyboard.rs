@@ -1,93 +1,255 @@
-use super::{Command, CommandGroup, Event, EventType, LoopCommand, TriggerCommand};
+use super::command_group::span;
+use super::event::asset_path;
+use super::load::{image_dimensions, load_profile};
+use super::parser::{lines, Parser};
+use super::{
+    BackgroundEvent, BreakEvent, Diagnostic, Event, EventType, LayerType, LoadProfile, ParseError,
+    Variable, VideoEvent,
+};
 
-#[derive(Debug)]
+use std::io::{self, Write};
+use std::mem;
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Storyboard {
+    pub variables: Vec<Variable>,
     pub events: Vec<Event>,
+
+    /* Beatmap only */
+    pub background: Option<BackgroundEvent>,
+    pub video: Option<VideoEvent>,
+    pub breaks: Vec<BreakEvent>,
 }
 
 impl Storyboard {
     pub fn new() -> Storyboard {
         Storyboard {
+            variables: Vec::<Variable>::new(),
             events: Vec::<Event>::new(),
+
+            background: None,
+            video: None,
+            breaks: Vec::<BreakEvent>::new(),
         }
     }
 
+    /// Parses a storyboard, failing on the first malformed line.
+    pub fn parse(data: &str) -> Result<Storyboard, ParseError> {
+        Storyboard::parse_with(data, &mut Parser::new(false))
+    }
+
+    /// Parses a storyboard the way osu! does, skipping malformed lines and
+    /// reporting them as diagnostics.
+    pub fn parse_lenient(data: &str) -> (Storyboard, Vec<Diagnostic>) {
+        let mut parser = Parser::new(true);
+        let storyboard = Storyboard::parse_with(data, &mut parser).unwrap_or_default();
+
+        (storyboard, parser.diagnostics)
+    }
+
+    /// Parses the storyboard of a whole `.osu` beatmap, only its `[Events]`
+    /// section is read.
+    pub fn parse_beatmap(data: &str) -> Result<Storyboard, ParseError> {
+        Storyboard::parse_with(data, &mut Parser::new(false).for_beatmap())
+    }
+
+    pub fn parse_beatmap_lenient(data: &str) -> (Storyboard, Vec<Diagnostic>) {
+        let mut parser = Parser::new(true).for_beatmap();
+        let storyboard = Storyboard::parse_with(data, &mut parser).unwrap_or_default();
+
+        (storyboard, parser.diagnostics)
+    }
+
     pub fn from_string(data: String) -> Storyboard {
-        let mut storyboard = Storyboard::new();
-        let mut root_group = CommandGroup::new();
-        let mut command_group = &mut root_group;
+        Storyboard::parse_lenient(&data).0
+    }
 
-        let mut last_event = &Event::new();
-        let events = data.split("\r\n");
-        for event in events {
-            if event.starts_with("//") || event.starts_with("[Events]") || event.trim().len() <= 0 {
-                continue;
+    /// First start and last end time over all events.
+    pub fn time_range(&self) -> Option<(i32, i32)> {
+        span(self.events.iter().filter_map(|event| event.time_range()))
+    }
+
+    /// Like `time_range`, but without trigger windows, see
+    /// `Event::command_range`.
+    pub fn command_range(&self) -> Option<(i32, i32)> {
+        span(self.events.iter().filter_map(|event| event.command_range()))
+    }
+
+    /// Estimates the storyboard load the way the osu! editor does: the summed
+    /// on-screen area of every visible sprite relative to the 640x480
+    /// playfield, sampled at every command boundary. `dimensions` returns the
+    /// pixel size of an image path as written in the storyboard, images
+    /// without one are skipped.
+    pub fn load_profile_with<F>(&self, dimensions: F) -> LoadProfile
+    where
+        F: FnMut(&Path) -> Option<(u32, u32)>,
+    {
+        load_profile(self, dimensions)
+    }
+
+    /// Like `load_profile_with`, reading the image sizes from the PNG and
+    /// JPEG headers in `assets`.
+    pub fn load_profile<P: AsRef<Path>>(&self, assets: P) -> LoadProfile {
+        let assets = assets.as_ref();
+
+        self.load_profile_with(|path| {
+            image_dimensions(&asset_path(assets, &path.to_string_lossy()))
+        })
+    }
+
+    /// Combines the shared `.osb` storyboard with the storyboard of a `.osu`
+    /// difficulty into the one osu! plays. Within each layer the `.osb`
+    /// events are drawn below the difficulty ones.
+    pub fn merge(osb: Storyboard, osu: Storyboard) -> Storyboard {
+        let mut events: Vec<(usize, Event)> = osb
+            .events
+            .into_iter()
+            .map(|event| (0, event))
+            .chain(osu.events.into_iter().map(|event| (1, event)))
+            .collect();
+        events.sort_by_key(|(source, event)| (Storyboard::draw_order(event), *source));
+
+        let mut variables = osb.variables;
+        variables.extend(osu.variables);
+
+        Storyboard {
+            variables,
+            events: events.into_iter().map(|(_, event)| event).collect(),
+
+            background: osu.background.or(osb.background),
+            video: osu.video.or(osb.video),
+            breaks: if osu.breaks.is_empty() {
+                osb.breaks
+            } else {
+                osu.breaks
+            },
+        }
+    }
+
+    fn draw_order(event: &Event) -> usize {
+        if event.etype == EventType::Sample {
+            return 6;
+        }
+
+        match event.layer {
+            LayerType::Background => 0,
+            LayerType::Fail => 1,
+            LayerType::Pass => 2,
+            LayerType::Foreground => 3,
+            LayerType::Overlay => 4,
+
+            _ => 5,
+        }
+    }
+
+    /// Writes the storyboard as an `[Events]` section. The output is normalized
+    /// to the layout osu! writes itself: events are grouped by layer in drawing
+    /// order with the samples last, keeping their order within each group.
+    /// Reading it back only gives an equal storyboard if the events already
+    /// were in that order.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let layers = [
+            ("//Storyboard Layer 0 (Background)", LayerType::Background),
+            ("//Storyboard Layer 1 (Fail)", LayerType::Fail),
+            ("//Storyboard Layer 2 (Pass)", LayerType::Pass),
+            ("//Storyboard Layer 3 (Foreground)", LayerType::Foreground),
+            ("//Storyboard Layer 4 (Overlay)", LayerType::Overlay),
+        ];
+
+        if !self.variables.is_empty() {
+            write!(writer, "[Variables]\r\n")?;
+            for variable in &self.variables {
+                write!(writer, "{}={}\r\n", variable.name, variable.value)?;
             }
+            write!(writer, "\r\n")?;
+        }
+
+        write!(writer, "[Events]\r\n")?;
+        write!(writer, "//Background and Video events\r\n")?;
+        if let Some(background) = &self.background {
+            write!(writer, "{}\r\n", background)?;
+        }
+        if let Some(video) = &self.video {
+            write!(writer, "{}\r\n", video)?;
+        }
+        if !self.breaks.is_empty() {
+            write!(writer, "//Break Periods\r\n")?;
+            for break_event in &self.breaks {
+                write!(writer, "{}\r\n", break_event)?;
+            }
+        }
+
+        for (header, layer) in layers.iter() {
+            write!(writer, "{}\r\n", header)?;
+            self.write_events(&mut writer, |event| {
+                event.etype != EventType::Sample && event.layer == *layer
+            })?;
+        }
+        self.write_events(&mut writer, |event| {
+            event.etype != EventType::Sample
+                && layers.iter().all(|(_, layer)| event.layer != *layer)
+        })?;
+
+        write!(writer, "//Storyboard Sound Samples\r\n")?;
+        self.write_events(&mut writer, |event| event.etype == EventType::Sample)?;
+
+        Ok(())
+    }
 
-            if event.to_string().starts_with(" ") {
-                let mut cmd_raw = event;
-                let mut depth = 0;
-
-                while cmd_raw.starts_with(" ") {
-                    depth += 1;
-                    cmd_raw = &cmd_raw[1..];
-                }
-
-                if depth < 2 {
-                    if last_event.etype == EventType::Unknown {
-                        continue;
-                    }
-
-                    command_group = &mut root_group;
-                }
-
-                let columns: Vec<&str> = cmd_raw.split(",").collect();
-                match columns[0] {
-                    "T" => {
-                        let name = columns[1];
-                        let mut start_time = 0;
-                        let mut end_time = 0;
-                        let mut group_number = 0;
-
-                        if columns.len() > 2 {
-                            start_time = columns[2].parse::<i32>().unwrap();
-                        }
-                        if columns.len() > 3 {
-                            end_time = columns[3].parse::<i32>().unwrap();
-                        }
-                        if columns.len() > 4 {
-                            group_number = columns[4].parse::<i32>().unwrap();
-                        }
-
-                        command_group = command_group.add_trigger(TriggerCommand::new(
-                            name.to_string(),
-                            start_time,
-                            end_time,
-                            group_number,
-                        ));
-                    }
-
-                    "L" => {
-                        let start_time = columns[1].parse::<i32>().unwrap();
-                        let count = columns[2].parse::<i32>().unwrap();
-                        command_group = command_group.add_loop(LoopCommand::new(start_time, count));
-                    }
-                    _ => command_group
-                        .commands
-                        .push(Command::from_string(cmd_raw.to_string())),
-                }
+    pub fn to_osb_string(&self) -> String {
+        let mut data = Vec::<u8>::new();
+        self.write_to(&mut data).unwrap();
 
+        String::from_utf8(data).unwrap()
+    }
+
+    fn write_events<W, F>(&self, writer: &mut W, filter: F) -> io::Result<()>
+    where
+        W: Write,
+        F: Fn(&Event) -> bool,
+    {
+        for event in self.events.iter().filter(|event| filter(event)) {
+            if event.etype == EventType::Unknown {
                 continue;
             }
 
-            let mut ev = Event::from_string(event.to_string());
-            ev.commands = root_group.commands.to_owned();
-            storyboard.events.push(ev);
-            last_event = storyboard.events.last_mut().unwrap();
-            root_group = CommandGroup::new();
-            command_group = &mut root_group;
+            write!(writer, "{}\r\n", event)?;
+            event.commands.write_to(writer, 1)?;
+        }
+
+        Ok(())
+    }
+
+    fn parse_with(data: &str, parser: &mut Parser) -> Result<Storyboard, ParseError> {
+        let mut storyboard = Storyboard::new();
+
+        for line in lines(data) {
+            if let Some(event) = parser.line(line)? {
+                storyboard.events.push(event);
+            }
         }
+        storyboard.events.extend(parser.finish());
+        storyboard.variables = mem::take(&mut parser.variables);
+        storyboard.background = parser.background.take();
+        storyboard.video = parser.video.take();
+        storyboard.breaks = mem::take(&mut parser.breaks);
+
+        Ok(storyboard)
+    }
+}
+
+impl Default for Storyboard {
+    fn default() -> Storyboard {
+        Storyboard::new()
+    }
+}
+
+impl FromStr for Storyboard {
+    type Err = ParseError;
 
-        storyboard
+    fn from_str(data: &str) -> Result<Storyboard, ParseError> {
+        Storyboard::parse(data)
     }
 }
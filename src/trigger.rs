@@ -0,0 +1,273 @@
+use super::TriggerCommand;
+
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SampleSet {
+    All,
+    Normal,
+    Soft,
+    Drum,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HitSoundAddition {
+    Whistle,
+    Finish,
+    Clap,
+}
+
+/// The parsed name of a `T` command.
+///
+/// Hit sound triggers are written as
+/// `HitSound[SampleSet][AdditionsSampleSet][Addition][CustomSampleIndex]`,
+/// every part left out matches any hit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TriggerCondition {
+    Passing,
+    Failing,
+    HitSound {
+        sample_set: Option<SampleSet>,
+        addition_set: Option<SampleSet>,
+        addition: Option<HitSoundAddition>,
+        custom_sample: Option<i32>,
+    },
+
+    Unknown(String),
+}
+
+/// A hit sound played during gameplay.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct HitSound {
+    pub sample_set: SampleSet,
+    pub addition_set: SampleSet,
+    pub whistle: bool,
+    pub finish: bool,
+    pub clap: bool,
+    pub custom_sample: i32,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GameplayEventKind {
+    HitSound(HitSound),
+    /// The player went from failing to passing.
+    Passing,
+    /// The player went from passing to failing.
+    Failing,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct GameplayEvent {
+    pub time: i32,
+    pub kind: GameplayEventKind,
+}
+
+/// A trigger group that fired, `trigger` indexes the trigger commands of the
+/// event. The group plays from `start_time` until its commands finished or
+/// another trigger of the same group number fired.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TriggerFiring {
+    pub trigger: usize,
+    pub group_number: i32,
+    pub start_time: i32,
+    pub end_time: i32,
+}
+
+impl SampleSet {
+    fn name(self) -> &'static str {
+        match self {
+            SampleSet::All => "All",
+            SampleSet::Normal => "Normal",
+            SampleSet::Soft => "Soft",
+            SampleSet::Drum => "Drum",
+        }
+    }
+
+    fn matches(set: Option<SampleSet>, played: SampleSet) -> bool {
+        match set {
+            None | Some(SampleSet::All) => true,
+            Some(set) => set == played,
+        }
+    }
+}
+
+impl HitSoundAddition {
+    fn name(self) -> &'static str {
+        match self {
+            HitSoundAddition::Whistle => "Whistle",
+            HitSoundAddition::Finish => "Finish",
+            HitSoundAddition::Clap => "Clap",
+        }
+    }
+}
+
+const SAMPLE_SETS: [SampleSet; 4] = [
+    SampleSet::All,
+    SampleSet::Normal,
+    SampleSet::Soft,
+    SampleSet::Drum,
+];
+
+const ADDITIONS: [HitSoundAddition; 3] = [
+    HitSoundAddition::Whistle,
+    HitSoundAddition::Finish,
+    HitSoundAddition::Clap,
+];
+
+impl TriggerCondition {
+    /// Names that can not be parsed become `Unknown`, osu! never fires them.
+    pub fn from_name(name: &str) -> TriggerCondition {
+        match name {
+            "Passing" => TriggerCondition::Passing,
+            "Failing" => TriggerCondition::Failing,
+
+            _ => name
+                .strip_prefix("HitSound")
+                .and_then(TriggerCondition::parse_hit_sound)
+                .unwrap_or_else(|| TriggerCondition::Unknown(name.to_string())),
+        }
+    }
+
+    fn parse_hit_sound(mut rest: &str) -> Option<TriggerCondition> {
+        let mut sample_set = None;
+        let mut addition_set = None;
+        let mut addition = None;
+        let mut custom_sample = None;
+
+        while !rest.is_empty() {
+            if let Some(set) = SAMPLE_SETS.iter().find(|set| rest.starts_with(set.name())) {
+                match (sample_set, addition_set) {
+                    (None, _) => sample_set = Some(*set),
+                    (Some(_), None) => addition_set = Some(*set),
+                    _ => return None,
+                }
+                rest = &rest[set.name().len()..];
+            } else if let Some(hit) = ADDITIONS.iter().find(|hit| rest.starts_with(hit.name())) {
+                if addition.is_some() {
+                    return None;
+                }
+                addition = Some(*hit);
+                rest = &rest[hit.name().len()..];
+            } else {
+                custom_sample = Some(rest.parse::<i32>().ok()?);
+                rest = "";
+            }
+        }
+
+        Some(TriggerCondition::HitSound {
+            sample_set,
+            addition_set,
+            addition,
+            custom_sample,
+        })
+    }
+
+    pub fn matches(&self, kind: &GameplayEventKind) -> bool {
+        match (self, kind) {
+            (TriggerCondition::Passing, GameplayEventKind::Passing) => true,
+            (TriggerCondition::Failing, GameplayEventKind::Failing) => true,
+            (
+                TriggerCondition::HitSound {
+                    sample_set,
+                    addition_set,
+                    addition,
+                    custom_sample,
+                },
+                GameplayEventKind::HitSound(hit),
+            ) => {
+                let addition = match addition {
+                    None => true,
+                    Some(HitSoundAddition::Whistle) => hit.whistle,
+                    Some(HitSoundAddition::Finish) => hit.finish,
+                    Some(HitSoundAddition::Clap) => hit.clap,
+                };
+
+                SampleSet::matches(*sample_set, hit.sample_set)
+                    && SampleSet::matches(*addition_set, hit.addition_set)
+                    && addition
+                    && custom_sample.is_none_or(|index| index == hit.custom_sample)
+            }
+
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for TriggerCondition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TriggerCondition::Passing => write!(f, "Passing"),
+            TriggerCondition::Failing => write!(f, "Failing"),
+            TriggerCondition::HitSound {
+                sample_set,
+                addition_set,
+                addition,
+                custom_sample,
+            } => {
+                write!(f, "HitSound")?;
+                if let Some(set) = sample_set {
+                    write!(f, "{}", set.name())?;
+                }
+                if let Some(set) = addition_set {
+                    write!(f, "{}", set.name())?;
+                }
+                if let Some(hit) = addition {
+                    write!(f, "{}", hit.name())?;
+                }
+                if let Some(index) = custom_sample {
+                    write!(f, "{}", index)?;
+                }
+
+                Ok(())
+            }
+
+            TriggerCondition::Unknown(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Works out which triggers fire for `events`. A trigger fires when a
+/// matching event happens within its window; only one trigger per group
+/// number plays at a time, a new firing cuts the previous one of its group
+/// short. When several triggers of a group match the same event the first
+/// one wins.
+pub fn evaluate_triggers(
+    triggers: &[TriggerCommand],
+    events: &[GameplayEvent],
+) -> Vec<TriggerFiring> {
+    let mut events: Vec<&GameplayEvent> = events.iter().collect();
+    events.sort_by_key(|event| event.time);
+
+    let mut firings = Vec::<TriggerFiring>::new();
+    let mut active = HashMap::<i32, usize>::new();
+
+    for event in events {
+        let mut fired = Vec::<i32>::new();
+
+        for (index, trigger) in triggers.iter().enumerate() {
+            if fired.contains(&trigger.group_number)
+                || !trigger.is_armed_at(event.time)
+                || !trigger.condition.matches(&event.kind)
+            {
+                continue;
+            }
+
+            if let Some(&previous) = active.get(&trigger.group_number) {
+                let previous = &mut firings[previous];
+                previous.end_time = previous.end_time.min(event.time);
+            }
+
+            active.insert(trigger.group_number, firings.len());
+            fired.push(trigger.group_number);
+            firings.push(TriggerFiring {
+                trigger: index,
+                group_number: trigger.group_number,
+                start_time: event.time,
+                end_time: event.time.saturating_add(trigger.duration()),
+            });
+        }
+    }
+
+    firings
+}
@@ -0,0 +1,47 @@
+use std::borrow::Cow;
+
+/// A `$name=value` entry of the `[Variables]` section, `name` keeps its `$`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variable {
+    pub name: String,
+    pub value: String,
+}
+
+impl Variable {
+    pub fn new(name: String, value: String) -> Variable {
+        Variable { name, value }
+    }
+
+    pub(crate) fn from_line(data: &str) -> Option<Variable> {
+        let mut pair = data.splitn(2, '=');
+        let name = pair.next()?.trim();
+        let value = pair.next()?.trim();
+
+        Some(Variable::new(name.to_string(), value.to_string()))
+    }
+
+    /// Replaces every variable in `data` like osu! does, repeating the
+    /// substitution while it keeps changing the line so variables may refer
+    /// to each other.
+    pub fn expand<'a>(variables: &[Variable], data: &'a str) -> Cow<'a, str> {
+        let mut line = Cow::Borrowed(data);
+
+        for _ in 0..=variables.len() {
+            if !line.contains('$') {
+                break;
+            }
+
+            let mut expanded = line.to_string();
+            for variable in variables {
+                expanded = expanded.replace(&variable.name, &variable.value);
+            }
+
+            if expanded == line {
+                break;
+            }
+            line = Cow::Owned(expanded);
+        }
+
+        line
+    }
+}
@@ -0,0 +1,380 @@
+use cgmath::Vector2;
+use storyboard::{
+    Command, CommandType, Easing, ParameterKind, ParseErrorKind, Payload, Rgb, Storyboard,
+};
+
+fn command(line: &str) -> Command {
+    line.parse::<Command>().unwrap()
+}
+
+fn values(line: &str) -> (f32, f32) {
+    let command = command(line);
+    (command.start_value().unwrap(), command.end_value().unwrap())
+}
+
+#[test]
+fn omitted_end_value_repeats_start() {
+    assert_eq!(values("F,0,1000,2000,1"), (1.0, 1.0));
+    assert_eq!(values("S,0,1000,2000,0.5"), (0.5, 0.5));
+    assert_eq!(values("R,0,1000,2000,-1.57"), (-1.57, -1.57));
+}
+
+#[test]
+fn explicit_end_value_is_honoured() {
+    assert_eq!(values("F,0,1000,2000,0,1"), (0.0, 1.0));
+    assert_eq!(values("S,0,1000,2000,0.5,1.25"), (0.5, 1.25));
+    assert_eq!(values("R,0,1000,2000,-2.5,2.5"), (-2.5, 2.5));
+}
+
+#[test]
+fn empty_end_value_repeats_start() {
+    assert_eq!(values("F,0,1000,2000,0.8,"), (0.8, 0.8));
+    assert_eq!(values("S,0,1000,2000,2, "), (2.0, 2.0));
+}
+
+#[test]
+fn command_types() {
+    assert_eq!(command("F,0,0,,1").ctype(), CommandType::Fade);
+    assert_eq!(command("S,0,0,,1").ctype(), CommandType::Scale);
+    assert_eq!(command("R,0,0,,1").ctype(), CommandType::Rotation);
+}
+
+#[test]
+fn empty_end_time_is_instant() {
+    let command = command("F,0,1500,,0,1");
+    assert_eq!((command.start_time, command.end_time), (1500, 1500));
+    assert_eq!(command.payload, Payload::Fade(0.0, 1.0));
+}
+
+#[test]
+fn padded_and_scientific_numbers() {
+    assert_eq!(values("F,0,0,100, 0.25 , 0.75 "), (0.25, 0.75));
+    assert_eq!(values("S,0,0,100,1E-05,2.5e1"), (0.00001, 25.0));
+    assert_eq!(values("R,0,0,100,.5,-.5"), (0.5, -0.5));
+}
+
+#[test]
+fn easing_is_kept() {
+    assert_eq!(command("F,3,0,100,0,1").easing, Easing::InQuad);
+    assert_eq!(command("R,34,0,100,0,1").easing, Easing::InOutBounce);
+}
+
+#[test]
+fn unknown_easing_is_linear() {
+    assert_eq!(command("F,99,0,100,1").easing, Easing::None);
+    assert_eq!(command("F,-1,0,100,1").easing, Easing::None);
+    assert!("F,abc,0,100,1".parse::<Command>().is_err());
+}
+
+#[test]
+fn invalid_values_are_errors() {
+    assert!("F,0,0,100".parse::<Command>().is_err());
+    assert!("F,0,0,100,abc".parse::<Command>().is_err());
+    assert!("S,0,0,100,1,abc".parse::<Command>().is_err());
+}
+
+#[test]
+fn writes_compact_form() {
+    for line in &[
+        "F,0,1000,2000,0,1",
+        "F,0,1000,,1",
+        "S,1,0,500,0.5,1.25",
+        "R,0,0,500,-2.5,2.5",
+    ] {
+        assert_eq!(command(line).to_string(), *line);
+    }
+
+    assert_eq!(command("F,0,1000,1000,1,1").to_string(), "F,0,1000,,1");
+}
+
+#[test]
+fn fades_evaluate_between_start_and_end() {
+    let storyboard = Storyboard::parse(
+        "[Events]\n\
+         Sprite,Foreground,Centre,\"sb/dot.png\",320,240\n \
+         F,0,1000,2000,0,1\n \
+         S,0,1000,2000,1,3\n \
+         R,0,1000,2000,0,2\n",
+    )
+    .unwrap();
+
+    let state = storyboard.events[0].state_at(1500.0);
+    assert_eq!(state.opacity, 0.5);
+    assert_eq!(state.scale.x, 2.0);
+    assert_eq!(state.rotation, 1.0);
+
+    let state = storyboard.events[0].state_at(2500.0);
+    assert_eq!(state.opacity, 1.0);
+    assert_eq!(state.scale.y, 3.0);
+}
+
+#[test]
+fn single_value_fade_holds() {
+    let storyboard = Storyboard::parse(
+        "[Events]\n\
+         Sprite,Foreground,Centre,\"sb/dot.png\",320,240\n \
+         F,0,1000,2000,0.6\n",
+    )
+    .unwrap();
+
+    assert_eq!(storyboard.events[0].state_at(1500.0).opacity, 0.6);
+    assert_eq!(storyboard.events[0].visible_range(), Some((1000, 2000)));
+}
+
+fn segments(line: &str) -> Vec<(i32, i32, f32, f32)> {
+    Command::parse_line(line)
+        .unwrap()
+        .iter()
+        .map(|command| {
+            (
+                command.start_time,
+                command.end_time,
+                command.start_value().unwrap(),
+                command.end_value().unwrap(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn extra_values_chain_segments() {
+    assert_eq!(
+        segments("F,0,1000,2000,0,1,0.5,0"),
+        vec![
+            (1000, 2000, 0.0, 1.0),
+            (2000, 3000, 1.0, 0.5),
+            (3000, 4000, 0.5, 0.0),
+        ]
+    );
+    assert_eq!(segments("S,0,0,100,1,2,"), vec![(0, 100, 1.0, 2.0)]);
+    assert_eq!(
+        segments("R,0,500,,0,1,2"),
+        vec![(500, 500, 0.0, 1.0), (500, 500, 1.0, 2.0)]
+    );
+}
+
+#[test]
+fn vector_segments() {
+    let commands = Command::parse_line("M,0,0,100,0,0,10,20,30,40").unwrap();
+    assert_eq!(commands.len(), 2);
+    assert_eq!((commands[1].start_time, commands[1].end_time), (100, 200));
+    assert_eq!(commands[1].start_vector(), commands[0].end_vector());
+    assert_eq!(commands[1].end_vector(), Some(Vector2::new(30.0, 40.0)));
+
+    // An incomplete group keeps the missing values of the one before
+    let commands = Command::parse_line("V,0,0,100,1,2,3").unwrap();
+    assert_eq!(commands.len(), 1);
+    assert_eq!(
+        commands[0].payload,
+        Payload::VectorScale(Vector2::new(1.0, 2.0), Vector2::new(3.0, 2.0))
+    );
+
+    let commands = Command::parse_line("C,0,0,100,255,255,255,0,0,0,255,0").unwrap();
+    assert_eq!(commands.len(), 2);
+    assert_eq!(
+        commands[1].payload,
+        Payload::Colour(Rgb::new(0.0, 0.0, 0.0), Rgb::new(255.0, 0.0, 0.0))
+    );
+}
+
+#[test]
+fn overflowing_segments_are_errors() {
+    let err = Command::parse_line("F,0,0,2000000000,0,1,0").unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::InvalidNumber);
+    assert_eq!((err.column, err.text.as_str()), (3, "2000000000"));
+
+    assert!(Command::parse_line("F,0,-2000000000,2000000000,0,1").is_err());
+    assert_eq!(
+        segments("F,0,0,1000000000,0,1,0"),
+        vec![
+            (0, 1000000000, 0.0, 1.0),
+            (1000000000, 2000000000, 1.0, 0.0)
+        ]
+    );
+}
+
+#[test]
+fn single_segment_parse_is_the_first_segment() {
+    assert_eq!(
+        command("F,0,1000,2000,0,1,0.5"),
+        Command::parse_line("F,0,1000,2000,0,1,0.5").unwrap()[0]
+    );
+}
+
+#[test]
+fn parser_expands_segments() {
+    let storyboard = Storyboard::parse(
+        "[Events]\n\
+         Sprite,Foreground,Centre,\"sb/dot.png\",320,240\n \
+         F,0,1000,2000,0,1,0\n",
+    )
+    .unwrap();
+
+    assert_eq!(storyboard.events[0].commands.commands.len(), 2);
+    assert_eq!(storyboard.events[0].state_at(2500.0).opacity, 0.5);
+    assert_eq!(storyboard.events[0].time_range(), Some((1000, 3000)));
+}
+
+#[test]
+fn parameters_are_typed() {
+    let parameter = command("P,0,1000,2000,H").parameter().unwrap();
+    assert_eq!(parameter.kind, ParameterKind::FlipHorizontal);
+    assert_eq!((parameter.start_time, parameter.end_time), (1000, 2000));
+
+    assert_eq!(
+        command("P,0,0,,V").payload,
+        Payload::Parameter(ParameterKind::FlipVertical)
+    );
+    assert_eq!(command("P,0,0,,A").ctype(), CommandType::BlendingMode);
+    assert_eq!(command("F,0,0,,1").parameter(), None);
+    assert_eq!(command("P,0,1000,2000,A").to_string(), "P,0,1000,2000,A");
+}
+
+#[test]
+fn unknown_parameter_is_an_error() {
+    assert!("P,0,0,,X".parse::<Command>().is_err());
+    assert!("P,0,0,".parse::<Command>().is_err());
+}
+
+#[test]
+fn parameter_is_active_between_start_and_end() {
+    let parameter = command("P,0,1000,2000,A").parameter().unwrap();
+    assert!(!parameter.is_active_at(999.0));
+    assert!(parameter.is_active_at(1000.0));
+    assert!(parameter.is_active_at(2000.0));
+    assert!(!parameter.is_active_at(2001.0));
+}
+
+#[test]
+fn parameter_without_duration_is_permanent() {
+    let parameter = command("P,0,1000,,H").parameter().unwrap();
+    assert!(!parameter.is_active_at(999.0));
+    assert!(parameter.is_active_at(1000.0));
+    assert!(parameter.is_active_at(1_000_000.0));
+
+    let parameter = command("P,0,1000,500,H").parameter().unwrap();
+    assert!(!parameter.is_active_at(500.0));
+    assert!(!parameter.is_active_at(1000.0));
+    assert!(!parameter.is_active_at(9999.0));
+}
+
+#[test]
+fn parameters_drive_sprite_state() {
+    let storyboard = Storyboard::parse(
+        "[Events]\n\
+         Sprite,Foreground,Centre,\"sb/dot.png\",320,240\n \
+         P,0,1000,2000,H\n \
+         P,0,1500,,V\n \
+         P,0,500,1000,A\n",
+    )
+    .unwrap();
+    let event = &storyboard.events[0];
+
+    let state = event.state_at(750.0);
+    assert_eq!(
+        (state.flip_horizontal, state.flip_vertical, state.additive),
+        (false, false, true)
+    );
+
+    let state = event.state_at(1750.0);
+    assert_eq!(
+        (state.flip_horizontal, state.flip_vertical, state.additive),
+        (true, true, false)
+    );
+
+    let state = event.state_at(5000.0);
+    assert_eq!(
+        (state.flip_horizontal, state.flip_vertical, state.additive),
+        (false, true, false)
+    );
+}
+
+#[test]
+fn loops_evaluate_like_their_unrolled_commands() {
+    let storyboard = Storyboard::parse(
+        "[Events]\n\
+         Sprite,Foreground,Centre,\"sb/dot.png\",320,240\n \
+         F,0,0,100,0,1\n \
+         L,1000,3\n  \
+         F,0,0,200,1,0\n  \
+         M,0,100,300,0,0,100,50\n  \
+         P,0,0,50,H\n \
+         L,1500,2\n  \
+         S,0,0,100,1,2\n \
+         R,0,2000,3000,0,1\n",
+    )
+    .unwrap();
+    let event = &storyboard.events[0];
+    let mut flattened = event.clone();
+    flattened.flatten_loops();
+
+    for time in (-100..4000).step_by(25) {
+        let time = f64::from(time);
+        assert_eq!(event.state_at(time), flattened.state_at(time), "{}", time);
+    }
+}
+
+#[test]
+fn long_loops_saturate_and_unroll_lazily() {
+    let storyboard = Storyboard::parse(
+        "[Events]\n\
+         Sprite,Foreground,Centre,\"sb/dot.png\",320,240\n \
+         L,0,100000000\n  \
+         F,0,0,1000,1\n",
+    )
+    .unwrap();
+    let event = &storyboard.events[0];
+    let loop_command = &event.commands.loop_commands[0];
+
+    assert_eq!(loop_command.time_range(), Some((0, i32::MAX)));
+    assert_eq!(
+        loop_command
+            .unroll()
+            .skip(2)
+            .map(|command| (command.start_time, command.end_time))
+            .next(),
+        Some((2000, 3000))
+    );
+    assert_eq!(event.state_at(1e12).opacity, 1.0);
+}
+
+#[test]
+fn visible_range_of_loops_matches_unrolled() {
+    for commands in &[
+        " L,1000,5\n  F,0,0,100,0,1\n  F,0,100,200,1,0\n",
+        " F,0,0,500,1\n L,1000,4\n  F,0,0,100,0\n  F,0,200,300,0\n",
+        " F,0,0,,0\n L,1000,3\n  F,0,0,100,0,1\n F,0,1150,,0\n",
+        " L,0,3\n  F,0,0,100,1\n L,50,3\n  F,0,0,100,0\n",
+        " L,500,2\n  F,0,0,100,0\n  M,0,0,400,0,0,1,1\n F,0,2000,2100,0,1\n",
+        " L,500,6\n  F,0,0,0,0\n  F,0,100,100,0\n",
+    ] {
+        let storyboard = Storyboard::parse(&format!(
+            "[Events]\nSprite,Foreground,Centre,\"sb/dot.png\",320,240\n{}",
+            commands
+        ))
+        .unwrap();
+        let event = &storyboard.events[0];
+        let mut flattened = event.clone();
+        flattened.flatten_loops();
+
+        assert_eq!(
+            event.visible_range(),
+            flattened.visible_range(),
+            "{}",
+            commands
+        );
+    }
+}
+
+#[test]
+fn visible_range_of_long_loops() {
+    let storyboard = Storyboard::parse(
+        "[Events]\n\
+         Sprite,Foreground,Centre,\"sb/dot.png\",320,240\n \
+         L,0,2000000000\n  \
+         F,0,0,1000,1\n",
+    )
+    .unwrap();
+
+    assert_eq!(storyboard.events[0].visible_range(), Some((0, i32::MAX)));
+}
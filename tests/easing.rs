@@ -0,0 +1,60 @@
+use storyboard::Easing;
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-3,
+        "{} is not {}",
+        actual,
+        expected
+    );
+}
+
+#[test]
+fn ids_round_trip() {
+    for id in 0..35 {
+        let easing = Easing::from_id(id).unwrap();
+        assert_eq!(easing.to_id(), id);
+    }
+
+    assert_eq!(Easing::from_id(3), Some(Easing::InQuad));
+    assert_eq!(Easing::from_id(34), Some(Easing::InOutBounce));
+    assert_eq!(Easing::from_id(35), None);
+    assert_eq!(Easing::from_id(-1), None);
+}
+
+#[test]
+fn curves_start_at_zero_and_end_at_one() {
+    for id in 0..35 {
+        let easing = Easing::from_id(id).unwrap();
+        assert_close(easing.apply(0.0), 0.0);
+        assert_close(easing.apply(1.0), 1.0);
+    }
+}
+
+#[test]
+fn curve_values() {
+    assert_close(Easing::None.apply(0.25), 0.25);
+    assert_close(Easing::In.apply(0.5), 0.25);
+    assert_close(Easing::InQuad.apply(0.5), 0.25);
+    assert_close(Easing::Out.apply(0.5), 0.75);
+    assert_close(Easing::OutQuad.apply(0.5), 0.75);
+    assert_close(Easing::InOutQuad.apply(0.25), 0.125);
+    assert_close(Easing::InCubic.apply(0.5), 0.125);
+    assert_close(Easing::OutCubic.apply(0.5), 0.875);
+    assert_close(Easing::InQuart.apply(0.5), 0.0625);
+    assert_close(Easing::InQuint.apply(0.5), 0.03125);
+    assert_close(Easing::OutSine.apply(0.5), 0.5f64.sqrt());
+    assert_close(Easing::InExpo.apply(0.5), 2f64.powf(-5.0));
+
+    for easing in &[
+        Easing::InOutQuad,
+        Easing::InOutCubic,
+        Easing::InOutQuart,
+        Easing::InOutQuint,
+        Easing::InOutSine,
+        Easing::InOutExpo,
+        Easing::InOutCirc,
+    ] {
+        assert_close(easing.apply(0.5), 0.5);
+    }
+}
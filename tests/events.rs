@@ -0,0 +1,28 @@
+use std::path::PathBuf;
+use storyboard::Storyboard;
+
+#[test]
+fn animation_frames_start_with_the_first_command() {
+    let storyboard = Storyboard::parse(
+        "[Events]\n\
+         Animation,Foreground,Centre,\"sb/anim.png\",320,240,3,100,LoopForever\n \
+         T,Passing,0,60000\n  \
+         F,0,0,100,1\n \
+         F,0,1000,2000,1\n",
+    )
+    .unwrap();
+    let event = &storyboard.events[0];
+
+    assert_eq!(
+        event.frame_at(500.0),
+        Some((0, PathBuf::from("sb/anim0.png")))
+    );
+    assert_eq!(
+        event.frame_at(1150.0),
+        Some((1, PathBuf::from("sb/anim1.png")))
+    );
+    assert_eq!(
+        event.frame_at(1300.0),
+        Some((0, PathBuf::from("sb/anim0.png")))
+    );
+}
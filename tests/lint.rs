@@ -0,0 +1,211 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+
+use storyboard::{lint, LintKind, Storyboard};
+
+fn kinds(data: &str) -> Vec<LintKind> {
+    let storyboard = Storyboard::parse(data).unwrap();
+    lint(&storyboard, None)
+        .iter()
+        .map(|lint| lint.kind)
+        .collect()
+}
+
+#[test]
+fn overlaps_within_loops() {
+    assert_eq!(
+        kinds(
+            "[Events]\n\
+             Sprite,Foreground,Centre,\"sb/dot.png\",320,240\n \
+             L,0,3\n  \
+             M,0,0,1000,0,0,1,1\n  \
+             M,0,500,1500,1,1,2,2\n"
+        ),
+        vec![LintKind::OverlappingCommands]
+    );
+    assert_eq!(
+        kinds(
+            "[Events]\n\
+             Sprite,Foreground,Centre,\"sb/dot.png\",320,240\n \
+             F,0,0,1500,1\n \
+             L,1000,100000000\n  \
+             F,0,0,1000,1\n"
+        ),
+        vec![LintKind::OverlappingCommands]
+    );
+}
+
+#[test]
+fn long_loops_are_linted_quickly() {
+    assert!(kinds(
+        "[Events]\n\
+         Sprite,Foreground,Centre,\"sb/dot.png\",320,240\n \
+         L,0,2147483647\n  \
+         F,0,0,1000,1\n"
+    )
+    .is_empty());
+}
+
+#[test]
+fn lints_every_kind() {
+    assert_eq!(
+        kinds(
+            "[Events]\n\
+             Sprite,Foreground,Centre,\"sb/dot.png\",320,240\n \
+             F,0,1000,500,1\n"
+        ),
+        vec![LintKind::EndBeforeStart]
+    );
+    assert_eq!(
+        kinds(
+            "[Events]\n\
+             Sprite,Foreground,Centre,\"sb/dot.png\",320,240\n \
+             F,0,0,1000,1\n \
+             F,0,500,1500,0.5\n"
+        ),
+        vec![LintKind::OverlappingCommands]
+    );
+    assert_eq!(
+        kinds(
+            "[Events]\n\
+             Sprite,Foreground,Centre,\"sb/dot.png\",320,240\n \
+             F,0,0,1000,0\n"
+        ),
+        vec![LintKind::NeverVisible]
+    );
+    assert_eq!(
+        kinds(
+            "[Events]\n\
+             Sprite,Foreground,Centre,\"sb/dot.png\",320,240\n \
+             L,0,5\n  \
+             F,0,0,,1\n"
+        ),
+        vec![LintKind::ZeroLengthLoop]
+    );
+    assert_eq!(
+        kinds(
+            "[Events]\n\
+             Sprite,Foreground,Centre,\"sb/dot.png\",320,240\n \
+             F,0,0,1000,1\n \
+             T,Foo,0,1000\n  \
+             S,0,0,100,2\n"
+        ),
+        vec![LintKind::UnknownTrigger]
+    );
+    assert_eq!(
+        kinds(
+            "[Events]\n\
+             Sample,0,Overlay,\"hit.wav\",100\n"
+        ),
+        vec![LintKind::WrongLayer]
+    );
+}
+
+#[test]
+fn missing_files_need_a_folder() {
+    let folder = folder("missing", &[]);
+    let storyboard = Storyboard::parse(
+        "[Events]\n\
+         Sprite,Foreground,Centre,\"sb/dot.png\",320,240\n \
+         F,0,0,1000,1\n",
+    )
+    .unwrap();
+
+    let lints = lint(&storyboard, Some(&folder));
+    fs::write(folder.join("sb").join("dot.png"), b"").unwrap();
+    let found = lint(&storyboard, Some(&folder));
+    fs::remove_dir_all(&folder).unwrap();
+
+    assert!(lint(&storyboard, None).is_empty());
+    assert_eq!(
+        lints.iter().map(|lint| lint.kind).collect::<Vec<_>>(),
+        vec![LintKind::MissingFile]
+    );
+    assert_eq!(
+        lints[0].to_string(),
+        "event 0: error: `sb/dot.png` does not exist [missing-file]"
+    );
+    assert!(found.is_empty());
+}
+
+/// A temporary beatmap folder holding `sb/dot.png` and the given storyboards.
+fn folder(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let folder = env::temp_dir().join(format!("storyboard-lint-{}-{}", name, process::id()));
+    fs::create_dir_all(folder.join("sb")).unwrap();
+
+    for (file, data) in files {
+        fs::write(folder.join(file), data).unwrap();
+    }
+
+    folder
+}
+
+/// Runs `storyboard-cli lint` in `folder`, returning the exit status and stdout.
+fn cli(folder: &Path, args: &[&str]) -> (bool, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_storyboard-cli"))
+        .arg("lint")
+        .args(args)
+        .current_dir(folder)
+        .output()
+        .unwrap();
+
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
+
+#[test]
+fn cli_prints_lints_and_fails_on_errors() {
+    let folder = folder(
+        "cli",
+        &[
+            (
+                "broken.osb",
+                "[Events]\n\
+                 Sprite,Foreground,Centre,\"sb/dot.png\",320,240\n \
+                 F,0,1000,500,1\n",
+            ),
+            (
+                "dim.osb",
+                "[Events]\n\
+                 Sprite,Foreground,Centre,\"sb/dot.png\",320,240\n \
+                 F,0,0,1000,0\n",
+            ),
+        ],
+    );
+    fs::write(folder.join("sb").join("dot.png"), b"").unwrap();
+
+    let broken = cli(&folder, &["broken.osb"]);
+    let json = cli(&folder, &["--format", "json", "broken.osb"]);
+    let dim = cli(&folder, &["dim.osb"]);
+    fs::remove_dir_all(&folder).unwrap();
+
+    assert_eq!(
+        broken,
+        (
+            false,
+            "broken.osb: event 0: error: `F,0,1000,500,1` ends before it starts [end-before-start]\n"
+                .to_string()
+        )
+    );
+    assert_eq!(
+        json,
+        (
+            false,
+            "[{\"file\":\"broken.osb\",\"line\":null,\"event\":0,\"kind\":\"end-before-start\",\
+             \"severity\":\"error\",\"message\":\"`F,0,1000,500,1` ends before it starts\"}]\n"
+                .to_string()
+        )
+    );
+    assert_eq!(
+        dim,
+        (
+            true,
+            "dim.osb: event 0: warning: `sb/dot.png` is never visible [never-visible]\n"
+                .to_string()
+        )
+    );
+}
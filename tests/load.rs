@@ -0,0 +1,70 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use storyboard::Storyboard;
+
+fn assets(name: &str) -> PathBuf {
+    let assets = env::temp_dir().join(format!("storyboard-{}-{}", name, std::process::id()));
+    fs::create_dir_all(assets.join("sb")).unwrap();
+
+    let mut png = vec![
+        0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n', 0, 0, 0, 13,
+    ];
+    png.extend_from_slice(b"IHDR");
+    png.extend_from_slice(&320u32.to_be_bytes());
+    png.extend_from_slice(&240u32.to_be_bytes());
+    png.extend_from_slice(&[8, 6, 0, 0, 0]);
+    fs::write(assets.join("sb").join("quarter.png"), png).unwrap();
+
+    // SOI, an APP0 segment to skip, then a baseline SOF0 of 640x120
+    let mut jpeg = vec![0xff, 0xd8, 0xff, 0xe0, 0, 6, b'J', b'F', b'I', b'F'];
+    jpeg.extend_from_slice(&[0xff, 0xff, 0xc0, 0, 11, 8, 0, 120, 2, 128, 1, 1, 0x11, 0]);
+    fs::write(assets.join("sb").join("strip.jpg"), jpeg).unwrap();
+
+    assets
+}
+
+fn peak(storyboard: &str, name: &str) -> Option<(i32, f64)> {
+    let assets = assets(name);
+    let profile = Storyboard::parse(storyboard).unwrap().load_profile(&assets);
+    fs::remove_dir_all(&assets).unwrap();
+
+    profile.peak.map(|peak| (peak.time, peak.load))
+}
+
+#[test]
+fn load_reads_png_and_jpeg_sizes() {
+    let storyboard = "[Events]\n\
+                      Sprite,Background,Centre,\"sb\\quarter.png\",320,240\n \
+                      F,0,0,1000,1\n\
+                      Sprite,Foreground,Centre,\"sb/strip.jpg\",320,240\n \
+                      F,0,500,1000,1\n\
+                      Sprite,Foreground,Centre,\"sb/missing.png\",320,240\n \
+                      F,0,0,1000,1\n";
+
+    assert_eq!(peak(storyboard, "sizes"), Some((500, 0.5)));
+}
+
+#[test]
+fn trigger_windows_do_not_count_towards_load() {
+    let storyboard = "[Events]\n\
+                      Sprite,Foreground,Centre,\"sb/quarter.png\",320,240\n \
+                      F,0,1000,2000,1\n \
+                      T,Passing,0,60000\n  \
+                      F,0,0,100,1\n\
+                      Sprite,Foreground,Centre,\"sb/strip.jpg\",320,240\n \
+                      F,0,30000,31000,1\n";
+
+    assert_eq!(peak(storyboard, "triggers"), Some((1000, 0.25)));
+}
+
+#[test]
+fn loops_count_towards_load() {
+    let storyboard = "[Events]\n\
+                      Sprite,Foreground,Centre,\"sb/quarter.png\",320,240\n \
+                      L,1000,3\n  \
+                      F,0,0,100,0,1\n  \
+                      S,0,100,200,1,2\n";
+
+    assert_eq!(peak(storyboard, "loops"), Some((1600, 1.0)));
+}
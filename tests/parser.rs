@@ -0,0 +1,249 @@
+use cgmath::Vector2;
+use storyboard::{
+    BackgroundEvent, BreakEvent, Event, EventType, LayerType, LoopType, OriginType, ParseErrorKind,
+    Severity, Storyboard, VideoEvent,
+};
+
+#[test]
+fn errors_report_line_and_column() {
+    let err = Storyboard::parse(
+        "[Events]\n\
+         Sprite,Foreground,Centre,\"sb/dot.png\",320,240\n \
+         F,0,abc,1000,1\n",
+    )
+    .unwrap_err();
+
+    assert_eq!(err.kind, ParseErrorKind::InvalidNumber);
+    assert_eq!((err.line, err.column), (3, 2));
+    assert_eq!(err.text, "abc");
+    assert_eq!(err.to_string(), "line 3, column 2: invalid number `abc`");
+}
+
+#[test]
+fn missing_columns_point_at_the_first_missing_one() {
+    let err = Storyboard::parse("[Events]\nSprite,Foreground\n").unwrap_err();
+
+    assert_eq!(err.kind, ParseErrorKind::MissingColumn);
+    assert_eq!((err.line, err.column), (2, 2));
+}
+
+#[test]
+fn strict_mode_fails_on_unknown_event_types() {
+    let err = Storyboard::parse("[Events]\nFoo,1,2\n").unwrap_err();
+
+    assert_eq!(err.kind, ParseErrorKind::UnknownEventType);
+    assert_eq!(err.line, 2);
+}
+
+#[test]
+fn lenient_mode_collects_diagnostics() {
+    let (storyboard, diagnostics) = Storyboard::parse_lenient(
+        "[Events]\n \
+         F,0,0,1000,1\n\
+         Foo,1,2\n\
+         Sprite,Foreground,Centre,\"sb/dot.png\",320,240\n \
+         F,0,abc,1000,1\n \
+         X,0,0,1000,1\n \
+         F,0,0,1000,1\n",
+    );
+
+    assert_eq!(storyboard.events.len(), 1);
+    assert_eq!(storyboard.events[0].commands.commands.len(), 1);
+
+    let found: Vec<(usize, Severity)> = diagnostics
+        .iter()
+        .map(|diagnostic| (diagnostic.line, diagnostic.severity))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (2, Severity::Warning),
+            (3, Severity::Warning),
+            (5, Severity::Error),
+            (6, Severity::Error),
+        ]
+    );
+    assert_eq!(
+        diagnostics[2].to_string(),
+        "line 5: error: invalid number `abc` in column 2"
+    );
+}
+
+#[test]
+fn commands_of_a_broken_event_are_skipped() {
+    let (storyboard, diagnostics) = Storyboard::parse_lenient(
+        "[Events]\n\
+         Sprite,Foreground,Centre,\"sb/dot.png\",abc,240\n \
+         F,0,0,1000,1\n\
+         Sprite,Foreground,Centre,\"sb/dot.png\",320,240\n \
+         F,0,0,1000,1\n",
+    );
+
+    assert_eq!(storyboard.events.len(), 1);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].line, 2);
+}
+
+#[test]
+fn from_string_does_not_panic() {
+    assert_eq!(
+        Event::from_string("Video,0,\"video.avi\"".to_string()).etype,
+        EventType::Unknown
+    );
+    assert_eq!(
+        Event::from_string("Foo,bar".to_string()).etype,
+        EventType::Unknown
+    );
+    assert_eq!(
+        Event::from_string("Sprite,Foreground,Centre,\"sb/dot.png\",320,240".to_string()).etype,
+        EventType::Sprite
+    );
+}
+
+#[test]
+fn commands_of_a_broken_group_are_skipped() {
+    let (storyboard, diagnostics) = Storyboard::parse_lenient(
+        "[Events]\n\
+         Sprite,Foreground,Centre,\"sb/dot.png\",320,240\n \
+         L,x,2\n  \
+         F,0,0,100,1\n \
+         T,HitSoundClap,abc\n  \
+         F,0,0,100,1\n \
+         M,0,0,100,1,1\n",
+    );
+
+    let commands = &storyboard.events[0].commands;
+    assert_eq!(commands.commands.len(), 1);
+    assert!(commands.loop_commands.is_empty());
+    assert!(commands.trigger_commands.is_empty());
+    assert_eq!(
+        diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.line)
+            .collect::<Vec<usize>>(),
+        vec![3, 5]
+    );
+}
+
+#[test]
+fn unknown_easing_is_kept_when_lenient() {
+    let (storyboard, diagnostics) = Storyboard::parse_lenient(
+        "[Events]\n\
+         Sprite,Foreground,Centre,\"sb/dot.png\",320,240\n \
+         F,99,0,1000,1\n \
+         F,0,0,1000,1\n",
+    );
+
+    assert_eq!(storyboard.events[0].commands.commands.len(), 2);
+    assert!(diagnostics.is_empty());
+}
+
+#[test]
+fn numeric_event_types_layers_and_origins() {
+    let storyboard = Storyboard::parse(
+        "[Events]\n\
+         4,3,1,\"sb/dot.png\",320,240\n\
+         6,0,9,\"sb/spin.png\",0,0,4,50,1\n\
+         5,1000,2,\"hit.wav\"\n",
+    )
+    .unwrap();
+
+    let found: Vec<(EventType, LayerType, OriginType)> = storyboard
+        .events
+        .iter()
+        .map(|event| (event.etype, event.layer, event.origin))
+        .collect();
+    assert_eq!(
+        found[..2],
+        [
+            (EventType::Sprite, LayerType::Foreground, OriginType::Centre),
+            (
+                EventType::Animation,
+                LayerType::Background,
+                OriginType::BottomRight
+            ),
+        ]
+    );
+    assert_eq!(storyboard.events[1].loop_type, LoopType::LoopOnce);
+    assert_eq!(storyboard.events[2].etype, EventType::Sample);
+    assert_eq!(storyboard.events[2].layer, LayerType::Pass);
+}
+
+const BEATMAP: &str = "osu file format v14\n\
+                       \n\
+                       [General]\n\
+                       AudioFilename: audio.mp3\n\
+                       \n\
+                       [Metadata]\n\
+                       Title:Sprite,Foreground\n\
+                       \n\
+                       [Events]\n\
+                       //Background and Video events\n\
+                       0,0,\"bg.jpg\",0,0\n\
+                       Video,-200,\"intro.avi\"\n\
+                       //Break Periods\n\
+                       2,5000,8000\n\
+                       Break,12000,15000\n\
+                       //Storyboard Layer 0 (Background)\n\
+                       Sprite,Background,Centre,\"sb/dot.png\",320,240\n \
+                       F,0,0,1000,1\n\
+                       \n\
+                       [TimingPoints]\n\
+                       0,500,4,2,0,100,1,0\n\
+                       \n\
+                       [HitObjects]\n\
+                       256,192,1000,1,0,0:0:0:0:\n";
+
+#[test]
+fn beatmaps_only_read_their_events() {
+    let storyboard = Storyboard::parse_beatmap(BEATMAP).unwrap();
+
+    assert_eq!(
+        storyboard.background,
+        Some(BackgroundEvent {
+            file_path: "bg.jpg".to_string(),
+            offset: Vector2::new(0.0, 0.0),
+        })
+    );
+    assert_eq!(
+        storyboard.video,
+        Some(VideoEvent {
+            time: -200,
+            file_path: "intro.avi".to_string(),
+            offset: Vector2::new(0.0, 0.0),
+        })
+    );
+    assert_eq!(
+        storyboard.breaks,
+        vec![
+            BreakEvent {
+                start_time: 5000,
+                end_time: 8000,
+            },
+            BreakEvent {
+                start_time: 12000,
+                end_time: 15000,
+            },
+        ]
+    );
+    assert_eq!(storyboard.events.len(), 1);
+    assert_eq!(storyboard.events[0].commands.commands.len(), 1);
+}
+
+#[test]
+fn lenient_beatmaps_report_broken_events() {
+    let beatmap = BEATMAP.replace("Break,12000,15000", "Break,12000,abc");
+
+    assert_eq!(Storyboard::parse_beatmap(&beatmap).unwrap_err().line, 15);
+
+    let (storyboard, diagnostics) = Storyboard::parse_beatmap_lenient(&beatmap);
+    assert_eq!(storyboard.breaks.len(), 1);
+    assert_eq!(storyboard.events.len(), 1);
+    assert!(storyboard.background.is_some() && storyboard.video.is_some());
+
+    let found: Vec<(usize, Severity)> = diagnostics
+        .iter()
+        .map(|diagnostic| (diagnostic.line, diagnostic.severity))
+        .collect();
+    assert_eq!(found, vec![(15, Severity::Error)]);
+}
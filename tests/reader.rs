@@ -0,0 +1,64 @@
+use std::io::BufReader;
+use storyboard::{Event, ParseErrorKind, Storyboard, StoryboardReader};
+
+const STORYBOARD: &str = "[Events]\r\n\
+                          Sprite,Foreground,Centre,\"sb/dot.png\",320,240\r\n \
+                          F,0,0,1000,0,1\r\n\r\n \
+                          S,0,0,,abc\r\n\
+                          Sprite,Background,Centre,\"sb/bg.png\",320,240\r \
+                          M,0,0,500,0,0,100,100\n \
+                          R,0,0,,1\r\n";
+
+fn read(data: &[u8], capacity: usize) -> (Vec<Event>, Vec<usize>) {
+    let mut reader = StoryboardReader::lenient(BufReader::with_capacity(capacity, data));
+    let events = reader.by_ref().collect::<Result<Vec<Event>, _>>().unwrap();
+    let lines = reader
+        .diagnostics()
+        .iter()
+        .map(|diagnostic| diagnostic.line)
+        .collect();
+
+    (events, lines)
+}
+
+#[test]
+fn line_endings_split_across_buffers() {
+    let (storyboard, diagnostics) = Storyboard::parse_lenient(STORYBOARD);
+    let lines: Vec<usize> = diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.line)
+        .collect();
+    assert_eq!(lines, vec![5]);
+
+    for capacity in 1..16 {
+        let (events, diagnostics) = read(STORYBOARD.as_bytes(), capacity);
+        assert_eq!(events, storyboard.events, "capacity {}", capacity);
+        assert_eq!(diagnostics, lines, "capacity {}", capacity);
+    }
+}
+
+const INVALID: &[u8] = b"[Events]\n\
+                         Sprite,Foreground,Centre,\"sb/dot.png\",320,240\n \
+                         F,0,0,1000,\xff\xfe\n \
+                         S,0,0,,2\n";
+
+#[test]
+fn invalid_utf8_is_an_error() {
+    let mut reader = StoryboardReader::new(BufReader::with_capacity(4, INVALID));
+    let err = reader.next().unwrap().unwrap_err();
+
+    assert_eq!(err.kind, ParseErrorKind::InvalidUtf8);
+    assert_eq!(err.line, 3);
+    assert!(reader.next().is_none());
+}
+
+#[test]
+fn invalid_utf8_is_skipped_when_lenient() {
+    let mut reader = StoryboardReader::lenient(BufReader::with_capacity(4, INVALID));
+    let events = reader.by_ref().collect::<Result<Vec<Event>, _>>().unwrap();
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].commands.commands.len(), 1);
+    assert_eq!(reader.diagnostics().len(), 1);
+    assert_eq!(reader.diagnostics()[0].line, 3);
+}
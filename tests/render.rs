@@ -0,0 +1,37 @@
+#![cfg(feature = "render")]
+
+use image::{Rgba, RgbaImage};
+use std::env;
+use std::fs;
+use storyboard::{Renderer, Storyboard};
+
+#[test]
+fn trigger_windows_do_not_extend_the_lifetime() {
+    let assets = env::temp_dir().join(format!("storyboard-render-{}", std::process::id()));
+    fs::create_dir_all(&assets).unwrap();
+    RgbaImage::from_pixel(8, 8, Rgba([255, 255, 255, 255]))
+        .save(assets.join("dot.png"))
+        .unwrap();
+
+    let storyboard = Storyboard::parse(
+        "[Events]\n\
+         Sprite,Foreground,Centre,\"dot.png\",320,240\n \
+         F,0,1000,2000,1\n \
+         T,Passing,0,60000\n  \
+         F,0,0,100,0\n",
+    )
+    .unwrap();
+    let mut renderer = Renderer::new(&assets);
+
+    assert_eq!(
+        renderer.render(&storyboard, 1500.0).get_pixel(320, 240).0[0],
+        255
+    );
+    assert_eq!(
+        renderer.render(&storyboard, 30000.0).get_pixel(320, 240).0[0],
+        0
+    );
+    assert_eq!(storyboard.command_range(), Some((1000, 2000)));
+
+    fs::remove_dir_all(&assets).unwrap();
+}
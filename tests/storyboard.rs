@@ -0,0 +1,110 @@
+use storyboard::{BreakEvent, LayerType, Storyboard};
+
+const STORYBOARD: &str = "[Variables]\n\
+                          $dot=\"sb/dot.png\"\n\
+                          [Events]\n\
+                          Sample,0,Foreground,\"sb/hit.wav\",100\n\
+                          Sprite,Foreground,Centre,$dot,320,240\n \
+                          F,0,0,1000,0,1\n\
+                          Animation,Background,TopLeft,\"sb/a.png\",0,0,4,50,LoopOnce\n \
+                          L,0,2\n  \
+                          M,0,0,500,0,0,100,100\n \
+                          T,HitSoundClap,0,5000\n  \
+                          C,0,0,100,255,0,0\n\
+                          Sprite,Background,Centre,\"sb/bg.png\",320,240\n \
+                          S,0,0,,0.5\n";
+
+#[test]
+fn written_storyboard_reads_back_in_layer_order() {
+    let storyboard = Storyboard::parse(STORYBOARD).unwrap();
+    let written = Storyboard::parse(&storyboard.to_osb_string()).unwrap();
+
+    assert_eq!(
+        written
+            .events
+            .iter()
+            .map(|event| event.layer)
+            .collect::<Vec<LayerType>>(),
+        vec![
+            LayerType::Background,
+            LayerType::Background,
+            LayerType::Foreground,
+            LayerType::Foreground,
+        ]
+    );
+    assert_eq!(written.events[0], storyboard.events[2]);
+    assert_eq!(written.events[1], storyboard.events[3]);
+    assert_eq!(written.events[2], storyboard.events[1]);
+    assert_eq!(written.events[3], storyboard.events[0]);
+    assert_eq!(written.variables, storyboard.variables);
+}
+
+#[test]
+fn normalized_storyboard_round_trips() {
+    let storyboard = Storyboard::parse(STORYBOARD).unwrap();
+    let written = Storyboard::parse(&storyboard.to_osb_string()).unwrap();
+
+    assert_eq!(
+        Storyboard::parse(&written.to_osb_string()).unwrap(),
+        written
+    );
+    assert_eq!(written.to_osb_string(), storyboard.to_osb_string());
+}
+
+#[test]
+fn merge_draws_the_osb_below_the_difficulty() {
+    let osb = Storyboard::parse(
+        "[Events]\n\
+         0,0,\"osb.jpg\",0,0\n\
+         2,1000,2000\n\
+         Sprite,Foreground,Centre,\"sb/osb-fg.png\",320,240\n\
+         Sample,0,Background,\"sb/osb.wav\"\n\
+         Sprite,Background,Centre,\"sb/osb-bg.png\",320,240\n",
+    )
+    .unwrap();
+    let osu = Storyboard::parse_beatmap(
+        "[Events]\n\
+         0,0,\"osu.jpg\",0,0\n\
+         2,5000,6000\n\
+         Sprite,Background,Centre,\"sb/osu-bg.png\",320,240\n\
+         Sample,0,Background,\"sb/osu.wav\"\n\
+         Sprite,Foreground,Centre,\"sb/osu-fg.png\",320,240\n",
+    )
+    .unwrap();
+
+    let merged = Storyboard::merge(osb, osu);
+
+    let files: Vec<&str> = merged
+        .events
+        .iter()
+        .map(|event| event.file_path.as_str())
+        .collect();
+    assert_eq!(
+        files,
+        vec![
+            "sb/osb-bg.png",
+            "sb/osu-bg.png",
+            "sb/osb-fg.png",
+            "sb/osu-fg.png",
+            "sb/osb.wav",
+            "sb/osu.wav",
+        ]
+    );
+    assert_eq!(merged.background.unwrap().file_path, "osu.jpg");
+    assert_eq!(
+        merged.breaks,
+        vec![BreakEvent {
+            start_time: 5000,
+            end_time: 6000,
+        }]
+    );
+}
+
+#[test]
+fn merge_keeps_the_osb_background_and_breaks_as_fallback() {
+    let osb = Storyboard::parse("[Events]\n0,0,\"osb.jpg\",0,0\n2,1000,2000\n").unwrap();
+    let merged = Storyboard::merge(osb, Storyboard::new());
+
+    assert_eq!(merged.background.unwrap().file_path, "osb.jpg");
+    assert_eq!(merged.breaks.len(), 1);
+}
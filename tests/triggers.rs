@@ -0,0 +1,213 @@
+use storyboard::{
+    evaluate_triggers, GameplayEvent, GameplayEventKind, HitSound, HitSoundAddition, SampleSet,
+    Storyboard, TriggerCommand, TriggerCondition,
+};
+
+fn hit_sound(name: &str) -> TriggerCondition {
+    TriggerCondition::from_name(name)
+}
+
+#[test]
+fn trigger_names() {
+    assert_eq!(hit_sound("Passing"), TriggerCondition::Passing);
+    assert_eq!(hit_sound("Failing"), TriggerCondition::Failing);
+    assert_eq!(
+        hit_sound("HitSound"),
+        TriggerCondition::HitSound {
+            sample_set: None,
+            addition_set: None,
+            addition: None,
+            custom_sample: None,
+        }
+    );
+    assert_eq!(
+        hit_sound("HitSoundDrumSoftWhistle2"),
+        TriggerCondition::HitSound {
+            sample_set: Some(SampleSet::Drum),
+            addition_set: Some(SampleSet::Soft),
+            addition: Some(HitSoundAddition::Whistle),
+            custom_sample: Some(2),
+        }
+    );
+    assert_eq!(
+        hit_sound("HitSoundClap"),
+        TriggerCondition::HitSound {
+            sample_set: None,
+            addition_set: None,
+            addition: Some(HitSoundAddition::Clap),
+            custom_sample: None,
+        }
+    );
+}
+
+#[test]
+fn unknown_trigger_names() {
+    for name in &[
+        "Foo",
+        "HitSoundFoo",
+        "HitSoundClapWhistle",
+        "HitSoundNormalSoftDrum",
+        "passing",
+    ] {
+        assert_eq!(hit_sound(name), TriggerCondition::Unknown(name.to_string()));
+    }
+}
+
+#[test]
+fn trigger_names_round_trip() {
+    for name in &[
+        "Passing",
+        "Failing",
+        "HitSound",
+        "HitSoundFinish",
+        "HitSoundSoft",
+        "HitSoundNormalDrumClap",
+        "HitSoundAllWhistle12",
+    ] {
+        assert_eq!(hit_sound(name).to_string(), *name);
+    }
+}
+
+fn clap(time: i32) -> GameplayEvent {
+    GameplayEvent {
+        time,
+        kind: GameplayEventKind::HitSound(HitSound {
+            sample_set: SampleSet::Normal,
+            addition_set: SampleSet::Normal,
+            whistle: false,
+            finish: false,
+            clap: true,
+            custom_sample: 0,
+        }),
+    }
+}
+
+fn triggers(commands: &str) -> Vec<TriggerCommand> {
+    let storyboard = Storyboard::parse(&format!(
+        "[Events]\nSprite,Foreground,Centre,\"sb/dot.png\",320,240\n{}",
+        commands
+    ))
+    .unwrap();
+
+    storyboard.events[0].commands.trigger_commands.clone()
+}
+
+fn fired(triggers: &[TriggerCommand], events: &[GameplayEvent]) -> Vec<(usize, i32, i32)> {
+    evaluate_triggers(triggers, events)
+        .iter()
+        .map(|firing| (firing.trigger, firing.start_time, firing.end_time))
+        .collect()
+}
+
+#[test]
+fn missing_window_bounds_are_open() {
+    let triggers = triggers(
+        " T,HitSoundClap,1000\n  F,0,0,100,1\n \
+         T,HitSoundClap,,2000,1\n  F,0,0,100,1\n \
+         T,HitSoundClap,,,2\n  F,0,0,100,1\n",
+    );
+
+    assert_eq!(
+        (triggers[0].start_time, triggers[0].end_time),
+        (Some(1000), None)
+    );
+    assert_eq!(
+        (triggers[1].start_time, triggers[1].end_time),
+        (None, Some(2000))
+    );
+    assert_eq!((triggers[2].start_time, triggers[2].end_time), (None, None));
+
+    assert_eq!(
+        fired(&triggers, &[clap(5000)]),
+        vec![(0, 5000, 5100), (2, 5000, 5100)]
+    );
+    assert_eq!(
+        fired(&triggers, &[clap(500)]),
+        vec![(1, 500, 600), (2, 500, 600)]
+    );
+
+    assert!(triggers.iter().all(|trigger| !trigger.is_bounded()));
+    assert_eq!(triggers[0].time_range(), None);
+    assert_eq!(triggers[1].time_range(), None);
+    assert_eq!(triggers[0].to_string(), "T,HitSoundClap,1000");
+    assert_eq!(triggers[1].to_string(), "T,HitSoundClap,,2000,1");
+    assert_eq!(triggers[2].to_string(), "T,HitSoundClap,,,2");
+}
+
+#[test]
+fn triggers_fire_within_their_window() {
+    let triggers = triggers(" T,HitSoundClap,1000,2000\n  F,0,0,100,1\n");
+
+    assert!(!triggers[0].is_armed_at(999));
+    assert!(triggers[0].is_armed_at(1000));
+    assert!(triggers[0].is_armed_at(2000));
+    assert!(!triggers[0].is_armed_at(2001));
+    assert_eq!(
+        fired(&triggers, &[clap(500), clap(1500), clap(2500)]),
+        vec![(0, 1500, 1600)]
+    );
+    assert!(triggers[0].is_bounded());
+    assert_eq!(triggers[0].time_range(), Some((1000, 2100)));
+}
+
+#[test]
+fn zero_window_is_always_armed() {
+    let triggers = triggers(" T,HitSoundClap,0,0\n  F,0,0,100,1\n");
+
+    assert!(triggers[0].is_armed_at(-1000));
+    assert!(triggers[0].is_armed_at(100_000));
+    assert!(!triggers[0].is_bounded());
+    assert_eq!(triggers[0].to_string(), "T,HitSoundClap,0,0");
+}
+
+#[test]
+fn unbounded_triggers_stay_out_of_the_time_range() {
+    let storyboard = Storyboard::parse(
+        "[Events]\n\
+         Sprite,Foreground,Centre,\"sb/dot.png\",320,240\n \
+         F,0,1000,2000,1\n \
+         T,HitSoundClap,0,0\n  \
+         F,0,0,100,1\n \
+         T,Passing,500\n  \
+         F,0,0,100,1\n \
+         T,Failing,3000,4000\n  \
+         F,0,0,100,1\n",
+    )
+    .unwrap();
+
+    assert_eq!(storyboard.time_range(), Some((1000, 4100)));
+    assert_eq!(storyboard.command_range(), Some((1000, 2000)));
+}
+
+#[test]
+fn firings_cut_off_their_group() {
+    let triggers = triggers(
+        " T,HitSoundClap,0,10000\n  F,0,0,1000,1\n \
+         T,HitSound,0,10000\n  F,0,0,1000,1\n \
+         T,Passing,0,10000,1\n  F,0,0,1000,1\n",
+    );
+    let passing = GameplayEvent {
+        time: 2200,
+        kind: GameplayEventKind::Passing,
+    };
+
+    // The first trigger of a group wins, the next firing ends the previous one
+    assert_eq!(
+        fired(&triggers, &[clap(2000), passing, clap(2500)]),
+        vec![(0, 2000, 2500), (2, 2200, 3200), (0, 2500, 3500)]
+    );
+}
+
+#[test]
+fn long_firings_saturate() {
+    let triggers = triggers(" T,Passing\n  F,0,0,2000000000,1\n");
+    let passing = GameplayEvent {
+        time: 1_000_000_000,
+        kind: GameplayEventKind::Passing,
+    };
+
+    assert_eq!(
+        fired(&triggers, &[passing]),
+        vec![(0, 1_000_000_000, i32::MAX)]
+    );
+}